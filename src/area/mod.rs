@@ -0,0 +1,412 @@
+//! Jet areas are determined by adding a large number of
+//! infinitesimally soft "ghost" particles to the event and counting
+//! how many of them end up in each jet, see
+//! [arXiv:0802.1188](https://arxiv.org/abs/0802.1188).
+//!
+//! * Active areas add all ghosts at once and let them cluster among
+//!   themselves and with the physical particles.
+//! * Passive areas add a single ghost at a time.
+//! * Voronoi areas don't use ghosts, but sum the areas of the Voronoi
+//!   cells of the jet constituents. They are much cheaper to compute
+//!   and coincide with passive areas for the kt algorithm.
+/// Voronoi areas
+pub mod voronoi;
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+use log::debug;
+use noisy_float::prelude::*;
+
+use crate::{
+    cluster::{ClusterHistory, ClusterSequence, IndexedClusterStep},
+    distance::{Distance, Geometry},
+    ClusterStep, PseudoJet,
+};
+
+pub use self::voronoi::{cluster_with_voronoi_area, voronoi_areas};
+
+/// Transverse momentum of the ghosts
+///
+/// This is small enough to not affect the momenta of physical
+/// jets, but large enough to avoid overflow in the distances of the
+/// common jet algorithms.
+pub const GHOST_PT: f64 = 1e-100;
+
+/// Grid of ghost particles used to determine active jet areas
+///
+/// The ghosts cover the rapidity range `-max_rap < y < max_rap`
+/// and the full azimuthal range. Each ghost is placed randomly
+/// inside its grid cell, where the cells have an area close to
+/// `ghost_area`. The random positions and the small fluctuations in
+/// the ghost transverse momenta are determined by `seed`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GhostSpec {
+    /// Maximum absolute rapidity of the ghosts
+    pub max_rap: N64,
+    /// Area of a single ghost in the rapidity-azimuth plane
+    pub ghost_area: N64,
+    /// Seed for the ghost positions and transverse momenta
+    pub seed: u64,
+}
+
+impl Default for GhostSpec {
+    fn default() -> Self {
+        Self {
+            max_rap: n64(6.),
+            ghost_area: n64(0.01),
+            seed: 0,
+        }
+    }
+}
+
+impl GhostSpec {
+    /// Number of grid cells in rapidity and azimuthal angle
+    pub fn grid_size(&self) -> [usize; 2] {
+        let cell_size = f64::from(self.ghost_area.sqrt());
+        let max_rap = f64::from(self.max_rap);
+        let nrap = (2. * max_rap / cell_size).ceil() as usize;
+        let nphi = (2. * PI / cell_size).ceil() as usize;
+        [nrap.max(1), nphi.max(1)]
+    }
+
+    /// Actual area of a single ghost
+    ///
+    /// This can differ slightly from `ghost_area` since the grid
+    /// cells have to cover the rapidity-azimuth plane exactly.
+    pub fn actual_ghost_area(&self) -> N64 {
+        let [nrap, nphi] = self.grid_size();
+        self.max_rap * 4. * PI / (nrap * nphi) as f64
+    }
+
+    /// Generate the ghosts
+    pub fn ghosts(&self) -> Vec<PseudoJet> {
+        let [nrap, nphi] = self.grid_size();
+        let drap = self.max_rap * 2. / nrap as f64;
+        let dphi = n64(2. * PI / nphi as f64);
+        let mut rng = SplitMix64(self.seed);
+        let mut ghosts = Vec::with_capacity(nrap * nphi);
+        for i in 0..nrap {
+            for j in 0..nphi {
+                let y = -self.max_rap + drap * (i as f64 + rng.next_f64());
+                let phi = dphi * (j as f64 + rng.next_f64());
+                let pt = n64(GHOST_PT * (0.95 + 0.1 * rng.next_f64()));
+                ghosts.push(PseudoJet::from_pt_y_phi_m(pt, y, phi, n64(0.)));
+            }
+        }
+        ghosts
+    }
+}
+
+/// A jet together with its area
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JetWithArea {
+    /// The jet
+    pub jet: PseudoJet,
+    /// Scalar area in the rapidity-azimuth plane
+    pub area: N64,
+    /// Area four-vector
+    ///
+    /// This is the sum of massless four-vectors along the
+    /// directions of the ghosts in the jet, each with a transverse
+    /// momentum equal to the ghost area.
+    pub area_4vector: PseudoJet,
+    /// Sum of `mt - pt` over the constituents, excluding ghosts
+    ///
+    /// This vanishes for jets made up of massless particles, even
+    /// if the jet itself is massive.
+    pub mt_minus_pt: N64,
+}
+
+// Area information for each entry in the clustering history
+#[derive(Copy, Clone, Debug, Default)]
+struct AreaEntry {
+    has_parton: bool,
+    nghosts: usize,
+    area_4vector: PseudoJet,
+    mt_minus_pt: N64,
+}
+
+/// Cluster `partons` into jets together with ghosts according to
+/// `ghost_spec` and determine the active area of each jet
+///
+/// Jets consisting only of ghosts are discarded. The remaining jets
+/// are returned in the order they were found. Since the ghosts are
+/// placed in the rapidity-azimuth plane, `distance` has to be based
+/// on the same geometry.
+pub fn cluster_with_active_area<D: Distance>(
+    partons: Vec<PseudoJet>,
+    distance: D,
+    ghost_spec: &GhostSpec,
+) -> Vec<JetWithArea> {
+    assert_eq!(
+        distance.geometry(),
+        Geometry::RapPhi,
+        "Active areas require a distance in the rapidity-azimuth plane"
+    );
+    let ghost_area = ghost_spec.actual_ghost_area();
+    let ghosts = ghost_spec.ghosts();
+    debug!("adding {} ghosts with area {ghost_area}", ghosts.len());
+    let mut areas = Vec::from_iter(partons.iter().map(|p| AreaEntry {
+        has_parton: true,
+        mt_minus_pt: p.mt() - p.pt(),
+        ..Default::default()
+    }));
+    areas.extend(ghosts.iter().map(|g| AreaEntry {
+        has_parton: false,
+        nghosts: 1,
+        area_4vector: ghost_area_4vector(g, ghost_area),
+        mt_minus_pt: n64(0.),
+    }));
+    let mut inputs = partons;
+    inputs.extend(ghosts);
+
+    let mut jets = Vec::new();
+    let mut clustering = ClusterHistory::new(inputs, distance);
+    while let Some(IndexedClusterStep { step, idx }) = clustering.next_indexed()
+    {
+        match step.step {
+            ClusterStep::Combine(_) => {
+                let [a, b] = idx.map(|i| areas[i]);
+                areas.push(AreaEntry {
+                    has_parton: a.has_parton || b.has_parton,
+                    nghosts: a.nghosts + b.nghosts,
+                    area_4vector: a.area_4vector + b.area_4vector,
+                    mt_minus_pt: a.mt_minus_pt + b.mt_minus_pt,
+                })
+            }
+            ClusterStep::Jet(jet) => {
+                let area = areas[idx[0]];
+                if area.has_parton {
+                    jets.push(JetWithArea {
+                        jet,
+                        area: ghost_area * area.nghosts as f64,
+                        area_4vector: area.area_4vector,
+                        mt_minus_pt: area.mt_minus_pt,
+                    })
+                }
+            }
+        }
+    }
+    jets
+}
+
+/// Cluster `partons` into jets and determine their passive areas
+/// with the ghosts defined by `ghost_spec`
+///
+/// The passive area is found by adding one ghost at a time to the
+/// partons and checking which jet it ends up in. The jets are the
+/// same as for [Cluster::cluster](crate::Cluster::cluster) and are
+/// returned in the same order.
+pub fn cluster_with_passive_area<D: Distance>(
+    partons: Vec<PseudoJet>,
+    distance: D,
+    ghost_spec: &GhostSpec,
+) -> Vec<JetWithArea> {
+    let ghost_area = ghost_spec.actual_ghost_area();
+    let npartons = partons.len();
+    let seq = ClusterSequence::new(partons.clone(), &distance);
+    let jet_pos = HashMap::<_, _>::from_iter(
+        seq.jets()
+            .iter()
+            .enumerate()
+            .map(|(pos, &jet)| (seq.constituents(jet), pos)),
+    );
+    let mut jets = Vec::from_iter(seq.jets().iter().map(|&jet| {
+        JetWithArea {
+            jet: seq.history()[jet].pseudojet,
+            area: n64(0.),
+            area_4vector: PseudoJet::new(),
+            mt_minus_pt: seq
+                .constituents(jet)
+                .iter()
+                .map(|&i| partons[i].mt() - partons[i].pt())
+                .sum(),
+        }
+    }));
+
+    let mut inputs = partons;
+    inputs.push(PseudoJet::new());
+    for ghost in ghost_spec.ghosts() {
+        inputs[npartons] = ghost;
+        let ghost_seq = ClusterSequence::new(inputs.clone(), &distance);
+        let with_ghost = ghost_seq
+            .jets()
+            .iter()
+            .map(|&jet| ghost_seq.constituents(jet))
+            .find(|constituents| constituents.last() == Some(&npartons))
+            .unwrap();
+        let constituents = &with_ghost[..with_ghost.len() - 1];
+        if let Some(&pos) = jet_pos.get(constituents) {
+            jets[pos].area += ghost_area;
+            jets[pos].area_4vector += ghost_area_4vector(&ghost, ghost_area);
+        }
+    }
+    jets
+}
+
+// Massless four-vector in the direction of `ghost` with transverse
+// momentum `ghost_area`
+fn ghost_area_4vector(ghost: &PseudoJet, ghost_area: N64) -> PseudoJet {
+    PseudoJet::from_pt_y_phi_m(ghost_area, ghost.rap(), ghost.phi(), n64(0.))
+}
+
+// Simple and fast pseudo-random number generator, see
+// https://prng.di.unimi.it/splitmix64.c
+#[derive(Copy, Clone, Debug)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // uniformly distributed in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        anti_kt_f, kt_f, pseudojet_f, test_data::*, Cluster, ClusterExt,
+    };
+
+    use super::*;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    fn coarse_ghosts() -> GhostSpec {
+        GhostSpec {
+            max_rap: n64(2.5),
+            ghost_area: n64(0.04),
+            seed: 1,
+        }
+    }
+
+    #[test]
+    fn tst_ghosts() {
+        let spec = coarse_ghosts();
+        let ghosts = spec.ghosts();
+        let [nrap, nphi] = spec.grid_size();
+        assert_eq!(ghosts.len(), nrap * nphi);
+        assert!(ghosts.iter().all(|g| g.rap().abs() < 2.5));
+        let total_area = spec.actual_ghost_area() * ghosts.len() as f64;
+        assert!((f64::from(total_area) - 10. * PI).abs() < 1e-10);
+        assert_eq!(ghosts, spec.ghosts());
+        let reseeded = GhostSpec { seed: 2, ..spec };
+        assert_ne!(ghosts, reseeded.ghosts());
+    }
+
+    #[test]
+    fn tst_single_jet_area() {
+        log_init();
+
+        let parton = pseudojet_f(100., 100., 0., 0.);
+        let jets = cluster_with_active_area(
+            vec![parton],
+            anti_kt_f(0.4),
+            &coarse_ghosts(),
+        );
+        assert_eq!(jets.len(), 1);
+        let jet = jets[0];
+        assert!((jet.jet.pt() - parton.pt()).abs() < 1e-10);
+        let expected = PI * 0.4 * 0.4;
+        assert!((f64::from(jet.area) / expected - 1.).abs() < 0.05);
+        assert!((jet.area_4vector.pt() - jet.area).abs() < 0.01);
+        assert!(jet.area_4vector.delta_r(&parton) < 0.05);
+    }
+
+    #[test]
+    fn tst_default_ghosts() {
+        log_init();
+
+        let partons = partons_9_to_7();
+        let spec = GhostSpec::default();
+        for r in [0.4, 1.] {
+            let jets =
+                cluster_with_active_area(partons.clone(), anti_kt_f(r), &spec);
+            let plain = partons.clone().cluster(anti_kt_f(r));
+            assert_eq!(jets.len(), plain.len());
+            for (jet, plain) in jets.iter().zip(plain) {
+                assert!(jet.jet.delta_r(&plain) < 1e-10);
+            }
+        }
+
+        // a hard anti-kt jet contains exactly the ghosts within R
+        let parton = pseudojet_f(100., 100., 0., 0.);
+        let jets =
+            cluster_with_active_area(vec![parton], anti_kt_f(0.4), &spec);
+        assert_eq!(jets.len(), 1);
+        let nghosts = spec
+            .ghosts()
+            .iter()
+            .filter(|g| g.delta_r(&parton) < 0.4)
+            .count();
+        assert_eq!(jets[0].area, spec.actual_ghost_area() * nghosts as f64);
+        let expected = PI * 0.4 * 0.4;
+        assert!((f64::from(jets[0].area) / expected - 1.).abs() < 0.05);
+    }
+
+    #[test]
+    fn tst_passive_area() {
+        log_init();
+
+        let parton = pseudojet_f(100., 100., 0., 0.);
+        let jets = cluster_with_passive_area(
+            vec![parton],
+            anti_kt_f(0.4),
+            &coarse_ghosts(),
+        );
+        assert_eq!(jets.len(), 1);
+        let expected = PI * 0.4 * 0.4;
+        assert!((f64::from(jets[0].area) / expected - 1.).abs() < 0.05);
+
+        let partons = partons_9_to_7();
+        let jets = cluster_with_passive_area(
+            partons.clone(),
+            anti_kt_f(0.4),
+            &coarse_ghosts(),
+        );
+        let plain = partons.clone().cluster(anti_kt_f(0.4));
+        assert_eq!(Vec::from_iter(jets.iter().map(|j| j.jet)), plain);
+    }
+
+    #[test]
+    fn tst_active_area() {
+        log_init();
+
+        let partons = partons_9_to_7();
+        let jets = partons
+            .clone()
+            .cluster_with_area(anti_kt_f(0.4), &coarse_ghosts());
+        let plain = partons.clone().cluster(anti_kt_f(0.4));
+        assert_eq!(jets.len(), plain.len());
+        for (jet, plain) in jets.iter().zip(plain) {
+            assert!(jet.jet.delta_r(&plain) < 1e-10);
+            assert!((jet.jet.pt() - plain.pt()).abs() < 1e-10);
+            if plain.rap().abs() < 2. {
+                assert!(jet.area > 0.);
+            }
+        }
+        // ghosts don't contribute to `mt - pt`
+        let mt_minus_pt: N64 = jets.iter().map(|j| j.mt_minus_pt).sum();
+        let expected: N64 = partons.iter().map(|p| p.mt() - p.pt()).sum();
+        assert!((mt_minus_pt - expected).abs() < 1e-10);
+
+        // kt areas fluctuate, but all ghosts end up in some jet
+        let spec = coarse_ghosts();
+        let jets = cluster_with_active_area(partons.clone(), kt_f(0.4), &spec);
+        assert!(!jets.is_empty());
+        assert!(jets.iter().all(|j| j.area >= 0.));
+        let total: N64 = jets.iter().map(|j| j.area).sum();
+        assert!(total <= spec.max_rap * 4. * PI + 1e-10);
+    }
+}
@@ -0,0 +1,231 @@
+use std::f64::consts::PI;
+
+use noisy_float::prelude::*;
+
+use crate::{
+    area::JetWithArea, cluster::ClusterSequence, distance::Distance, PseudoJet,
+};
+
+/// Areas of the Voronoi cells of the given particles in the
+/// rapidity-azimuth cylinder
+///
+/// Each cell is intersected with a disc of radius `max_radius`
+/// around the particle, so that isolated particles have a finite
+/// area. `max_radius` has to be below π/2. Particles at exactly the
+/// same position in rapidity and azimuthal angle do not restrict each
+/// other's cells.
+pub fn voronoi_areas(partons: &[PseudoJet], max_radius: N64) -> Vec<N64> {
+    assert!(max_radius > 0. && max_radius < PI / 2.);
+    let r = f64::from(max_radius);
+    let coord = Vec::from_iter(
+        partons
+            .iter()
+            .map(|p| [f64::from(p.rap()), f64::from(p.phi())]),
+    );
+    Vec::from_iter(
+        coord
+            .iter()
+            .map(|&centre| n64(cell_area(centre, &coord, r))),
+    )
+}
+
+/// Cluster `partons` into jets and determine their Voronoi areas
+///
+/// The area of each jet is the sum of the [voronoi_areas] of its
+/// constituents. The area four-vector is the sum of the constituent
+/// momenta, each rescaled to a transverse momentum equal to its
+/// area. Constituents without transverse momentum contribute to the
+/// scalar area only. The jets are the same as for
+/// [Cluster::cluster](crate::Cluster::cluster) and are returned in
+/// the same order.
+pub fn cluster_with_voronoi_area<D: Distance>(
+    partons: Vec<PseudoJet>,
+    distance: D,
+    max_radius: N64,
+) -> Vec<JetWithArea> {
+    let areas = voronoi_areas(&partons, max_radius);
+    let area_4vectors =
+        Vec::from_iter(partons.iter().zip(&areas).map(|(p, &area)| {
+            if p.pt2() > 0. {
+                *p * (area * p.inv_pt())
+            } else {
+                PseudoJet::new()
+            }
+        }));
+    let mt_minus_pt = Vec::from_iter(partons.iter().map(|p| p.mt() - p.pt()));
+    let seq = ClusterSequence::new(partons, distance);
+    Vec::from_iter(seq.jets().iter().map(|&jet| {
+        let constituents = seq.constituents(jet);
+        JetWithArea {
+            jet: seq.history()[jet].pseudojet,
+            area: constituents.iter().map(|&i| areas[i]).sum(),
+            area_4vector: constituents.iter().map(|&i| area_4vectors[i]).sum(),
+            mt_minus_pt: constituents.iter().map(|&i| mt_minus_pt[i]).sum(),
+        }
+    }))
+}
+
+// Area of the Voronoi cell around `centre` intersected with a disc of
+// radius `r`
+fn cell_area(centre: [f64; 2], coord: &[[f64; 2]], r: f64) -> f64 {
+    // positions relative to the centre, using the periodic image
+    // closest in φ
+    let mut rel = Vec::from_iter(coord.iter().filter_map(|&[y, phi]| {
+        let dy = y - centre[0];
+        let mut dphi = phi - centre[1];
+        if dphi > PI {
+            dphi -= 2. * PI;
+        } else if dphi < -PI {
+            dphi += 2. * PI;
+        }
+        let dist2 = dy * dy + dphi * dphi;
+        (dist2 > 0. && dist2 < 8. * r * r).then_some((dist2, [dy, dphi]))
+    }));
+    rel.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+
+    // start from a square enclosing the disc and cut away the
+    // half-planes closer to other particles
+    let mut cell = vec![[-r, -r], [r, -r], [r, r], [-r, r]];
+    let mut max_vertex_dist2 = 2. * r * r;
+    for (dist2, d) in rel {
+        // the bisector doesn't intersect the cell
+        if dist2 > 4. * max_vertex_dist2 {
+            break;
+        }
+        cell = clip(&cell, d, dist2 / 2.);
+        if cell.is_empty() {
+            return 0.;
+        }
+        max_vertex_dist2 =
+            cell.iter().map(|&[x, y]| x * x + y * y).fold(0., f64::max);
+    }
+    polygon_disc_area(&cell, r)
+}
+
+// Clip a convex polygon to the half-plane x·n <= c
+fn clip(polygon: &[[f64; 2]], n: [f64; 2], c: f64) -> Vec<[f64; 2]> {
+    let dist = |[x, y]: [f64; 2]| x * n[0] + y * n[1] - c;
+    let mut res = Vec::with_capacity(polygon.len() + 1);
+    for (i, &p) in polygon.iter().enumerate() {
+        let q = polygon[(i + 1) % polygon.len()];
+        let (dp, dq) = (dist(p), dist(q));
+        if dp <= 0. {
+            res.push(p);
+        }
+        if (dp < 0. && dq > 0.) || (dp > 0. && dq < 0.) {
+            let t = dp / (dp - dq);
+            res.push([p[0] + t * (q[0] - p[0]), p[1] + t * (q[1] - p[1])]);
+        }
+    }
+    res
+}
+
+// Area of the intersection between a polygon and a disc of radius `r`
+// around the origin
+//
+// The polygon is decomposed into triangles spanned by the origin and
+// each edge. Edges are split at the intersection points with the
+// circle; pieces inside the disc contribute the triangle area, pieces
+// outside the circular sector area.
+fn polygon_disc_area(polygon: &[[f64; 2]], r: f64) -> f64 {
+    let r2 = r * r;
+    let mut area = 0.;
+    for (i, &a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        let mut points = vec![a];
+        points.extend(circle_crossings(a, b, r2));
+        points.push(b);
+        for piece in points.windows(2) {
+            let [p, q] = [piece[0], piece[1]];
+            let cross = p[0] * q[1] - p[1] * q[0];
+            let mid = [(p[0] + q[0]) / 2., (p[1] + q[1]) / 2.];
+            // pieces inside the disc have their midpoint strictly
+            // inside, unless they are so short that both
+            // contributions agree
+            if mid[0] * mid[0] + mid[1] * mid[1] < r2 * (1. - 1e-12) {
+                area += cross / 2.;
+            } else {
+                let dot = p[0] * q[0] + p[1] * q[1];
+                area += r2 * cross.atan2(dot) / 2.;
+            }
+        }
+    }
+    area.abs()
+}
+
+// Points where the segment from `a` to `b` crosses the circle of
+// radius r around the origin, ordered from `a` to `b`
+fn circle_crossings(a: [f64; 2], b: [f64; 2], r2: f64) -> Vec<[f64; 2]> {
+    let d = [b[0] - a[0], b[1] - a[1]];
+    let qa = d[0] * d[0] + d[1] * d[1];
+    let qb = 2. * (a[0] * d[0] + a[1] * d[1]);
+    let qc = a[0] * a[0] + a[1] * a[1] - r2;
+    let disc = qb * qb - 4. * qa * qc;
+    if qa == 0. || disc <= 0. {
+        return Vec::new();
+    }
+    let sqrt_disc = disc.sqrt();
+    [(-qb - sqrt_disc) / (2. * qa), (-qb + sqrt_disc) / (2. * qa)]
+        .into_iter()
+        .filter(|t| *t > 0. && *t < 1.)
+        .map(|t| [a[0] + t * d[0], a[1] + t * d[1]])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{anti_kt_f, test_data::*, Cluster};
+
+    use super::*;
+
+    fn assert_close(a: N64, b: f64) {
+        assert!(f64::from((a - b).abs()) < 1e-10, "{a} != {b}");
+    }
+
+    #[test]
+    fn tst_voronoi_single() {
+        let p = PseudoJet::from_pt_y_phi_m(n64(10.), n64(1.), n64(2.), n64(0.));
+        assert_close(voronoi_areas(&[p], n64(0.4))[0], PI * 0.16);
+    }
+
+    #[test]
+    fn tst_voronoi_pair() {
+        // circle with a segment at distance h from the centre cut away
+        let (r, h) = (0.4f64, 0.15f64);
+        let segment = r * r * (h / r).acos() - h * (r * r - h * h).sqrt();
+        let expected = PI * r * r - segment;
+
+        let pt = n64(10.);
+        // across the φ = 0 boundary
+        let partons = [
+            PseudoJet::from_pt_y_phi_m(pt, n64(0.5), n64(h), n64(0.)),
+            PseudoJet::from_pt_y_phi_m(pt, n64(0.5), n64(-h), n64(0.)),
+        ];
+        let areas = voronoi_areas(&partons, n64(r));
+        assert_close(areas[0], expected);
+        assert_close(areas[1], expected);
+
+        let jets =
+            cluster_with_voronoi_area(partons.to_vec(), anti_kt_f(0.4), n64(r));
+        assert_eq!(jets.len(), 1);
+        assert_close(jets[0].area, 2. * expected);
+        assert_close(jets[0].area_4vector.pt(), 2. * expected * h.cos());
+        assert_close(jets[0].area_4vector.rap(), 0.5);
+    }
+
+    #[test]
+    fn tst_voronoi_jets() {
+        let partons = partons_9_to_7();
+        let jets = cluster_with_voronoi_area(
+            partons.clone(),
+            anti_kt_f(0.4),
+            n64(0.4),
+        );
+        let plain = partons.clone().cluster(anti_kt_f(0.4));
+        assert_eq!(Vec::from_iter(jets.iter().map(|j| j.jet)), plain);
+        let total: N64 = voronoi_areas(&partons, n64(0.4)).into_iter().sum();
+        let jet_total: N64 = jets.iter().map(|j| j.area).sum();
+        assert_close(total, jet_total.into());
+        assert!(jets.iter().all(|j| j.area > 0.));
+    }
+}
@@ -0,0 +1,176 @@
+use std::f64::consts::PI;
+
+use log::debug;
+use noisy_float::prelude::*;
+
+use crate::{
+    background::{median_and_sigma, Background},
+    cluster::geom_tile::Tiling,
+    PseudoJet,
+};
+
+/// Background estimation from the median transverse momentum density
+/// in a fixed grid of cells in the rapidity-azimuth plane
+///
+/// In contrast to [BackgroundEstimator](super::BackgroundEstimator),
+/// no clustering is needed.
+///
+/// # Examples
+///
+/// ```rust
+/// use jetty::{background::GridBackgroundEstimator, pseudojet_f};
+/// use noisy_float::prelude::*;
+///
+/// let particles = vec![
+///     pseudojet_f(0.2626773221934335, -0.08809521946454194, -0.1141608706693822, -0.2195584284654444),
+///     pseudojet_f(2.21902459329915, -0.7529973704809976, -0.9658189214109036, -1.850475321845671)
+/// ];
+///
+/// let estimator = GridBackgroundEstimator::new(n64(2.5), n64(0.55));
+/// assert_eq!(estimator.estimate(&particles).rho(), 0.);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GridBackgroundEstimator {
+    max_rap: N64,
+    cell_size: N64,
+}
+
+impl GridBackgroundEstimator {
+    /// Estimator using particles with an absolute rapidity below
+    /// `max_rap`
+    ///
+    /// The rapidity range and the full azimuthal range are divided
+    /// into cells with a size close to `cell_size` in both rapidity
+    /// and azimuthal angle. Panics unless both `max_rap` and
+    /// `cell_size` are positive.
+    pub fn new(max_rap: N64, cell_size: N64) -> Self {
+        assert!(max_rap > 0. && cell_size > 0.);
+        Self { max_rap, cell_size }
+    }
+
+    /// Estimate the background from `particles`
+    ///
+    /// Empty cells count as cells with vanishing density. The mass
+    /// density is computed from `mt - pt` of the particles.
+    pub fn estimate(&self, particles: &[PseudoJet]) -> Background {
+        let Self { max_rap, cell_size } = *self;
+        let nbins = |range: N64| {
+            let n = f64::from((range / cell_size).round());
+            (n as usize).max(1)
+        };
+        let tiling = Tiling {
+            max_rap,
+            n_rap_bins: nbins(max_rap * 2.),
+            n_phi_bins: nbins(n64(2. * PI)),
+        };
+        let ncells = tiling.n_rap_bins * tiling.n_phi_bins;
+        let cell_area = max_rap * 4. * PI / ncells as f64;
+        debug!("estimating background in {ncells} cells with area {cell_area}");
+
+        let mut pt = vec![n64(0.); ncells];
+        let mut mt_minus_pt = vec![n64(0.); ncells];
+        for p in particles.iter().filter(|p| p.rap().abs() < max_rap) {
+            let [rap_idx, phi_idx] = tiling.tile_coord(p);
+            let idx = rap_idx * tiling.n_phi_bins + phi_idx;
+            pt[idx] += p.pt();
+            mt_minus_pt[idx] += p.mt() - p.pt();
+        }
+        pt.sort_unstable();
+        mt_minus_pt.sort_unstable();
+        let [rho, sigma] = median_and_sigma(&pt, cell_area);
+        let [rho_m, sigma_m] = median_and_sigma(&mt_minus_pt, cell_area);
+        Background {
+            rho: rho / cell_area,
+            sigma: sigma / cell_area,
+            rho_m: rho_m / cell_area,
+            sigma_m: sigma_m / cell_area,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        area::{GhostSpec, JetWithArea},
+        test_data::*,
+    };
+
+    use super::*;
+
+    #[test]
+    fn tst_grid_background() {
+        let max_rap = n64(2.);
+        // one massive particle with pt = 0.5 in the centre of each cell
+        let mut particles = Vec::new();
+        for i in 0..8 {
+            for j in 0..12 {
+                let y = n64(-2. + 0.5 * (i as f64 + 0.5));
+                let phi = n64(2. * PI * (j as f64 + 0.5) / 12.);
+                particles.push(PseudoJet::from_pt_y_phi_m(
+                    n64(0.5),
+                    y,
+                    phi,
+                    n64(1.2),
+                ));
+            }
+        }
+        // 8 bins in rapidity and 12 in azimuth
+        let cell_size = n64(PI / 6.);
+        let cell_area = 0.5 * 2. * PI / 12.;
+        let mt_minus_pt = (0.25f64 + 1.44).sqrt() - 0.5;
+
+        let mut with_hard = particles.clone();
+        with_hard.extend(partons_9_to_7());
+        let bkg = GridBackgroundEstimator::new(max_rap, cell_size)
+            .estimate(&with_hard);
+        assert!((f64::from(bkg.rho()) - 0.5 / cell_area).abs() < 1e-10);
+        assert!(bkg.sigma() >= 0.);
+        assert!(
+            (f64::from(bkg.rho_m()) - mt_minus_pt / cell_area).abs() < 1e-10
+        );
+
+        // particles outside the rapidity range are ignored
+        let bkg = GridBackgroundEstimator::new(n64(1.), cell_size)
+            .estimate(&particles);
+        assert!((f64::from(bkg.rho()) - 0.5 / cell_area).abs() < 1e-10);
+        assert!(bkg.sigma().abs() < 1e-10);
+
+        // mostly empty cells
+        let bkg = GridBackgroundEstimator::new(max_rap, cell_size)
+            .estimate(&particles[..10]);
+        assert_eq!(bkg.rho(), 0.);
+    }
+
+    #[test]
+    fn tst_grid_subtract() {
+        let spec = GhostSpec {
+            max_rap: n64(2.),
+            ghost_area: n64(0.01),
+            seed: 5,
+        };
+        // soft uniform background with known density
+        let particles = Vec::from_iter(
+            spec.ghosts()
+                .into_iter()
+                .map(|g| g * (0.01 / f64::from(g.pt()))),
+        );
+        let bkg = GridBackgroundEstimator::new(n64(2.), n64(0.5))
+            .estimate(&particles);
+        let rho = 0.01 / f64::from(spec.actual_ghost_area());
+        assert!((f64::from(bkg.rho()) / rho - 1.).abs() < 0.1);
+
+        let hard =
+            PseudoJet::from_pt_y_phi_m(n64(50.), n64(0.2), n64(1.), n64(0.));
+        let area = n64(0.5);
+        let area_4vector =
+            PseudoJet::from_pt_y_phi_m(area, n64(0.2), n64(1.), n64(0.));
+        let jet = JetWithArea {
+            jet: hard + area_4vector * rho,
+            area,
+            area_4vector,
+            mt_minus_pt: n64(0.),
+        };
+        let subtracted = bkg.subtract(&jet);
+        assert!((subtracted.pt() - hard.pt()).abs() < area * rho * 0.1);
+    }
+}
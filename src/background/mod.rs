@@ -0,0 +1,351 @@
+//! Pileup and underlying event contribute a soft, roughly uniform
+//! background to each jet. Its transverse momentum density ρ per
+//! unit area in the rapidity-azimuth plane is estimated from the
+//! median over many jets, see
+//! [arXiv:0707.1378](https://arxiv.org/abs/0707.1378). A jet is then
+//! corrected by subtracting ρ times its area.
+//!
+//! * [BackgroundEstimator](crate::background::BackgroundEstimator)
+//!   determines ρ from jets with areas.
+//! * [GridBackgroundEstimator](crate::background::GridBackgroundEstimator)
+//!   determines ρ from a fixed grid of cells and is much faster,
+//!   since no clustering is required.
+/// Grid-based background estimation
+pub mod grid;
+
+use std::cmp::{max, Reverse};
+use std::f64::consts::PI;
+
+use log::debug;
+use noisy_float::prelude::*;
+
+use crate::{area::JetWithArea, PseudoJet};
+
+pub use self::grid::GridBackgroundEstimator;
+
+/// Background transverse momentum and mass densities with their
+/// fluctuations
+///
+/// This is the result of [BackgroundEstimator::estimate] and
+/// [GridBackgroundEstimator::estimate].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Background {
+    rho: N64,
+    sigma: N64,
+    rho_m: N64,
+    sigma_m: N64,
+}
+
+impl Background {
+    /// Median transverse momentum density ρ
+    pub fn rho(&self) -> N64 {
+        self.rho
+    }
+
+    /// Fluctuations σ of the transverse momentum density
+    ///
+    /// This is defined such that the fluctuations in a jet with area
+    /// A are of the order σ·√A.
+    pub fn sigma(&self) -> N64 {
+        self.sigma
+    }
+
+    /// Median density ρ_m of `mt - pt`
+    pub fn rho_m(&self) -> N64 {
+        self.rho_m
+    }
+
+    /// Fluctuations σ_m of the density of `mt - pt`
+    pub fn sigma_m(&self) -> N64 {
+        self.sigma_m
+    }
+
+    /// Subtract the background from `jet`
+    ///
+    /// See [subtract] for details.
+    pub fn subtract(&self, jet: &JetWithArea) -> PseudoJet {
+        subtract(jet, self.rho, self.rho_m)
+    }
+}
+
+/// Background estimation from the median transverse momentum density
+/// of jets
+///
+/// # Examples
+///
+/// ```rust
+/// use jetty::{area::GhostSpec, background::BackgroundEstimator, kt_f, pseudojet_f, ClusterExt};
+/// use noisy_float::prelude::*;
+///
+/// let partons = vec![
+///     pseudojet_f(0.2626773221934335, -0.08809521946454194, -0.1141608706693822, -0.2195584284654444),
+///     pseudojet_f(2.21902459329915, -0.7529973704809976, -0.9658189214109036, -1.850475321845671)
+/// ];
+/// let ghosts = GhostSpec { max_rap: n64(2.), ghost_area: n64(0.1), seed: 0 };
+/// let jets = partons.cluster_with_area(kt_f(0.4), &ghosts);
+///
+/// let background = BackgroundEstimator::new(n64(1.5), 2).estimate(&jets);
+/// let subtracted = background.subtract(&jets[0]);
+/// assert!(subtracted.pt() <= jets[0].jet.pt());
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BackgroundEstimator {
+    max_rap: N64,
+    n_hardest: usize,
+}
+
+impl BackgroundEstimator {
+    /// Estimator using jets with an absolute rapidity below
+    /// `max_rap`, excluding the `n_hardest` jets with the largest
+    /// transverse momentum
+    pub fn new(max_rap: N64, n_hardest: usize) -> Self {
+        Self { max_rap, n_hardest }
+    }
+
+    /// Estimate the background from `jets`
+    ///
+    /// Jets with vanishing area are ignored. The part of the
+    /// rapidity range not covered by any jet is taken into account
+    /// through additional empty jets with the average jet area. This
+    /// means that jets consisting only of ghosts can, but don't have
+    /// to be included.
+    ///
+    /// The mass density ρ_m is the median of `mt - pt` summed over
+    /// the constituents of each jet and divided by its area, as for
+    /// the [GridBackgroundEstimator].
+    pub fn estimate(&self, jets: &[JetWithArea]) -> Background {
+        let max_rap = self.max_rap;
+        let mut jets = Vec::from_iter(
+            jets.iter()
+                .filter(|j| j.jet.rap().abs() < max_rap && j.area > 0.),
+        );
+        // the area of the excluded hardest jets is not empty
+        let empty_area =
+            max_rap * 4. * PI - jets.iter().map(|j| j.area).sum::<N64>();
+        jets.sort_unstable_by_key(|j| Reverse(j.jet.pt2()));
+        let jets = jets.get(self.n_hardest..).unwrap_or_default();
+        if jets.is_empty() {
+            return Background::default();
+        }
+        let jet_area: N64 = jets.iter().map(|j| j.area).sum();
+        let mean_area = jet_area / jets.len() as f64;
+        let nempty = max(empty_area / mean_area, n64(0.)).floor();
+        let nempty = f64::from(nempty) as usize;
+        debug!(
+            "estimating background from {} jets and {nempty} empty jets",
+            jets.len()
+        );
+
+        let densities = |density: fn(&JetWithArea) -> N64| {
+            let mut res = vec![n64(0.); nempty];
+            res.extend(jets.iter().map(|j| density(j)));
+            res.sort_unstable();
+            res
+        };
+        let [rho, sigma] =
+            median_and_sigma(&densities(|j| j.jet.pt() / j.area), mean_area);
+        let [rho_m, sigma_m] =
+            median_and_sigma(&densities(|j| j.mt_minus_pt / j.area), mean_area);
+        Background {
+            rho,
+            sigma,
+            rho_m,
+            sigma_m,
+        }
+    }
+}
+
+/// Subtract a background with transverse momentum density `rho` and
+/// mass density `rho_m` from `jet`
+///
+/// The transverse components of the area four-vector are rescaled
+/// with `rho`, the longitudinal and energy components with `rho +
+/// rho_m`. If the subtracted background has at least the transverse
+/// momentum of the original jet, a pseudojet with vanishing
+/// four-momentum is returned instead.
+pub fn subtract(jet: &JetWithArea, rho: N64, rho_m: N64) -> PseudoJet {
+    let a = &jet.area_4vector;
+    let rho_long = rho + rho_m;
+    let background = PseudoJet::from([
+        rho_long * a.e(),
+        rho * a.px(),
+        rho * a.py(),
+        rho_long * a.pz(),
+    ]);
+    if background.pt2() >= jet.jet.pt2() {
+        return PseudoJet::new();
+    }
+    let mut res = jet.jet - background;
+    if let Some(idx) = jet.jet.user_index() {
+        res.set_user_index(idx);
+    }
+    res
+}
+
+// Median and the fluctuations from the distance to the 16th
+// percentile, normalised to `mean_area`
+//
+// `sorted` has to be non-empty and in ascending order.
+pub(crate) fn median_and_sigma(sorted: &[N64], mean_area: N64) -> [N64; 2] {
+    let median = quantile(sorted, 0.5);
+    let lower = quantile(sorted, (1. - 0.6827) / 2.);
+    [median, (median - lower) * mean_area.sqrt()]
+}
+
+// Linearly interpolated quantile of a sorted non-empty slice
+fn quantile(sorted: &[N64], q: f64) -> N64 {
+    let pos = q * (sorted.len() - 1) as f64;
+    let lower = pos.floor() as usize;
+    let frac = pos - lower as f64;
+    match sorted.get(lower + 1) {
+        Some(&upper) => sorted[lower] * (1. - frac) + upper * frac,
+        None => sorted[lower],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        area::{cluster_with_active_area, GhostSpec},
+        kt_f,
+        test_data::*,
+    };
+
+    use super::*;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn tst_quantile() {
+        let values = [1., 2., 4., 8.].map(n64);
+        assert_eq!(quantile(&values, 0.5), 3.);
+        assert_eq!(quantile(&values, 0.), 1.);
+        assert_eq!(quantile(&values, 1.), 8.);
+        assert_eq!(quantile(&values[..1], 0.5), 1.);
+    }
+
+    #[test]
+    fn tst_uniform_background() {
+        log_init();
+
+        // fake jets with uniform density ρ = 2 covering the whole range
+        let max_rap = n64(2.);
+        let mut jets = Vec::new();
+        for i in 0..20 {
+            for j in 0..10 {
+                let y = n64(-1.9 + 0.2 * i as f64);
+                let phi = n64(2. * PI * (j as f64 + 0.5) / 10.);
+                let area = n64(0.2 * 2. * PI / 10.);
+                let area_4vector =
+                    PseudoJet::from_pt_y_phi_m(area, y, phi, n64(0.));
+                jets.push(JetWithArea {
+                    jet: area_4vector * 2.,
+                    area,
+                    area_4vector,
+                    mt_minus_pt: n64(0.),
+                });
+            }
+        }
+        let hard =
+            PseudoJet::from_pt_y_phi_m(n64(100.), n64(0.), n64(1.), n64(0.));
+        jets[0].jet = hard + jets[0].area_4vector * 2.;
+
+        let bkg = BackgroundEstimator::new(max_rap, 1).estimate(&jets);
+        assert!((bkg.rho() - 2.).abs() < 1e-10);
+        assert!(bkg.sigma().abs() < 1e-10);
+        assert!(bkg.rho_m().abs() < 1e-10);
+
+        let subtracted = bkg.subtract(&jets[0]);
+        for i in 0..4 {
+            assert!((subtracted[i] - hard[i]).abs() < 1e-10);
+        }
+        assert!(bkg.subtract(&jets[1]).pt() < 1e-10);
+
+        // most of the range is empty
+        let sparse =
+            Vec::from_iter(jets.iter().copied().filter(|j| j.jet.rap() > 1.));
+        let bkg = BackgroundEstimator::new(max_rap, 0).estimate(&sparse);
+        assert_eq!(bkg.rho(), 0.);
+
+        // most of the range is covered
+        let dense =
+            Vec::from_iter(jets.iter().copied().filter(|j| j.jet.rap() > -1.));
+        let bkg = BackgroundEstimator::new(max_rap, 1).estimate(&dense);
+        assert!((bkg.rho() - 2.).abs() < 1e-10);
+
+        // the excluded hardest jets still cover their area
+        let mut many_hard = jets.clone();
+        for jet in &mut many_hard[..120] {
+            jet.jet += hard;
+        }
+        let bkg = BackgroundEstimator::new(max_rap, 120).estimate(&many_hard);
+        assert!((bkg.rho() - 2.).abs() < 1e-10);
+    }
+
+    #[test]
+    fn tst_event_background() {
+        log_init();
+
+        let spec = GhostSpec {
+            max_rap: n64(2.5),
+            ghost_area: n64(0.04),
+            seed: 3,
+        };
+        let mut partons = partons_9_to_7();
+        // add a soft uniform background
+        let bkg_particles = GhostSpec {
+            ghost_area: n64(0.5),
+            ..spec
+        };
+        partons.extend(
+            bkg_particles
+                .ghosts()
+                .into_iter()
+                .map(|g| g * (0.5 / f64::from(g.pt()))),
+        );
+        let jets = cluster_with_active_area(partons, kt_f(0.4), &spec);
+        let bkg = BackgroundEstimator::new(n64(2.), 2).estimate(&jets);
+        let expected = 0.5 / f64::from(bkg_particles.actual_ghost_area());
+        assert!((f64::from(bkg.rho()) / expected - 1.).abs() < 0.3);
+        assert!(bkg.sigma() >= 0.);
+        assert!(bkg.rho_m() >= 0.);
+    }
+
+    #[test]
+    fn tst_massless_background() {
+        log_init();
+
+        let spec = GhostSpec {
+            max_rap: n64(2.5),
+            ghost_area: n64(0.04),
+            seed: 4,
+        };
+        // massless particles, which form massive jets
+        let partons = Vec::from_iter(
+            GhostSpec {
+                ghost_area: n64(0.1),
+                ..spec
+            }
+            .ghosts()
+            .into_iter()
+            .map(|g| g * (0.5 / f64::from(g.pt()))),
+        );
+        let jets = cluster_with_active_area(partons, kt_f(0.4), &spec);
+        let bkg = BackgroundEstimator::new(n64(2.), 0).estimate(&jets);
+        assert!(bkg.rho() > 0.);
+        assert!(bkg.rho_m().abs() < 1e-10);
+        assert!(bkg.sigma_m().abs() < 1e-10);
+
+        // using `mt - pt` of the jets instead would give a finite ρ_m
+        let jet_mt_minus_pt =
+            Vec::from_iter(jets.iter().map(|j| JetWithArea {
+                mt_minus_pt: j.jet.mt() - j.jet.pt(),
+                ..*j
+            }));
+        let bkg =
+            BackgroundEstimator::new(n64(2.), 0).estimate(&jet_mt_minus_pt);
+        assert!(bkg.rho_m() > 1e-3);
+    }
+}
@@ -3,32 +3,56 @@ use std::cmp::min;
 use log::{debug, trace};
 use noisy_float::{prelude::Float, types::N64};
 
-use crate::{distance::Distance, ClusterStep, PseudoJet};
+use crate::{
+    cluster::{
+        sealed::Sealed, ClusterHistExt, ClusterStepWithDist, IndexedClusterStep,
+    },
+    distance::{Distance, Geometry},
+    recombine::{EScheme, Recombiner},
+    ClusterStep, PseudoJet,
+};
 
 /// Cluster history using the geometric O(N^2) approach of [arXiv:0512210](https://arxiv.org/abs/hep-ph/0512210)
 #[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
-pub struct ClusterGeom<D> {
+pub struct ClusterGeom<D, R = EScheme> {
     pseudojets: Vec<PseudoJetWithDist>,
     distance: D,
+    recombiner: R,
+    next_id: usize,
 }
 
 impl<D: Distance> ClusterGeom<D> {
     /// Initialise clustering for the given `partons` and `distance`
     pub fn new(partons: Vec<PseudoJet>, distance: D) -> Self {
+        Self::with_recombiner(partons, distance, EScheme)
+    }
+}
+
+impl<D: Distance, R: Recombiner> ClusterGeom<D, R> {
+    /// Initialise clustering for the given `partons`, `distance`, and
+    /// `recombiner`
+    pub fn with_recombiner(
+        partons: Vec<PseudoJet>,
+        distance: D,
+        recombiner: R,
+    ) -> Self {
         let mut pseudojets =
-            Vec::from_iter(partons.into_iter().map(|pseudojet| {
-                PseudoJetWithDist {
+            Vec::from_iter(partons.into_iter().enumerate().map(
+                |(id, pseudojet)| PseudoJetWithDist {
                     pseudojet,
+                    id,
                     ..Default::default()
-                }
-            }));
+                },
+            ));
+        let geometry = distance.geometry();
         for i in 0..pseudojets.len() {
             pseudojets[i].beam_dist =
                 distance.beam_distance(&pseudojets[i].pseudojet);
             let mut nearest_gdist = N64::max_value();
             let mut nearest_idx = usize::MAX;
             for j in (0..i).chain((i + 1)..pseudojets.len()) {
-                let gdist = pseudojets[i].delta_r2(&pseudojets[j]);
+                let gdist =
+                    pseudojets[i].geometric_distance(&pseudojets[j], geometry);
                 if gdist < nearest_gdist {
                     nearest_gdist = gdist;
                     nearest_idx = j;
@@ -47,8 +71,10 @@ impl<D: Distance> ClusterGeom<D> {
             }
         }
         Self {
+            next_id: pseudojets.len(),
             pseudojets,
             distance,
+            recombiner,
         }
     }
 
@@ -135,11 +161,12 @@ impl<D: Distance> ClusterGeom<D> {
         assert!(pos < self.pseudojets.len());
         self.remove_nearest_link(pos);
 
+        let geometry = self.distance.geometry();
         let others = (0..pos).chain((pos + 1)..self.pseudojets.len());
         let nearest_idx = others
             .map(|idx| {
-                let gdist =
-                    self.pseudojets[pos].delta_r2(&self.pseudojets[idx]);
+                let gdist = self.pseudojets[pos]
+                    .geometric_distance(&self.pseudojets[idx], geometry);
                 (gdist, idx)
             })
             .min_by_key(|(d, _)| *d)
@@ -161,6 +188,7 @@ impl<D: Distance> ClusterGeom<D> {
         let beam_dist = self.distance.beam_distance(&pseudojet);
         let mut pseudojet = PseudoJetWithDist {
             pseudojet,
+            id: self.next_id,
             beam_dist,
             nearest_dist: N64::max_value(),
             ..Default::default()
@@ -214,33 +242,57 @@ impl<D: Distance> ClusterGeom<D> {
     }
 }
 
-impl<D: Distance> Iterator for ClusterGeom<D> {
+impl<D: Distance, R: Recombiner> Iterator for ClusterGeom<D, R> {
     type Item = ClusterStep;
 
     /// Perform the next clustering step
     fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_dist().map(|s| s.step)
+    }
+}
+
+impl<D, R> Sealed for ClusterGeom<D, R> {}
+
+impl<D: Distance, R: Recombiner> ClusterHistExt for ClusterGeom<D, R> {
+    fn pseudojets(&self) -> Vec<PseudoJet> {
+        Vec::from_iter(self.pseudojets.iter().map(|p| p.pseudojet))
+    }
+
+    fn next_dist(&self) -> Option<N64> {
+        let i = self.min_idx()?;
+        Some(self.pseudojets[i].step_dist())
+    }
+
+    fn next_indexed(&mut self) -> Option<IndexedClusterStep> {
         trace!("pseudojets: {:#?}", self.pseudojets);
         let i = self.min_idx()?;
         let pi = self.remove(i);
-        if pi.beam_dist < pi.nearest_dist {
+        let dist = pi.step_dist();
+        let (step, idx) = if pi.is_jet() {
+            let id = pi.id;
             let pi = pi.pseudojet;
             debug!("new jet: {pi:?}");
-            Some(pi.into())
+            (pi.into(), [id, id])
         } else {
             let j = pi.nearest_neighbour_idx;
             debug!("cluster pseudojets {i} {j}");
             let pj = self.remove(j);
+            let ids = [pi.id, pj.id];
             let pi = pi.pseudojet;
             let pj = pj.pseudojet;
-            self.push(pi + pj);
-            Some([pi, pj].into())
-        }
+            self.push(self.recombiner.recombine(&pi, &pj));
+            self.next_id += 1;
+            ([pi, pj].into(), ids)
+        };
+        let step = ClusterStepWithDist { step, dist };
+        Some(IndexedClusterStep { step, idx })
     }
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
 struct PseudoJetWithDist {
     pseudojet: PseudoJet,
+    id: usize,
     beam_dist: N64,
     nearest_dist: N64,
     nearest_neighbour_idx: usize,
@@ -251,8 +303,30 @@ impl PseudoJetWithDist {
         min(self.nearest_dist, self.beam_dist)
     }
 
-    fn delta_r2(&self, p: &PseudoJetWithDist) -> N64 {
-        self.pseudojet.delta_r2(&p.pseudojet)
+    // whether the next clustering step involving this pseudojet
+    // turns it into a jet
+    fn is_jet(&self) -> bool {
+        // without a beam distance the last pseudojet has no
+        // neighbour left to be combined with
+        self.nearest_neighbour_idx == usize::MAX
+            || self.beam_dist < self.nearest_dist
+    }
+
+    // distance of the next clustering step involving this pseudojet
+    fn step_dist(&self) -> N64 {
+        if self.is_jet() {
+            self.beam_dist
+        } else {
+            self.nearest_dist
+        }
+    }
+
+    fn geometric_distance(
+        &self,
+        p: &PseudoJetWithDist,
+        geometry: Geometry,
+    ) -> N64 {
+        geometry.distance(&self.pseudojet, &p.pseudojet)
     }
 }
 
@@ -270,7 +344,10 @@ impl Ord for PseudoJetWithDist {
 
 #[cfg(test)]
 mod tests {
-    use crate::{anti_kt_f, cluster::naive::ClusterNaive, test_data::*};
+    use crate::{
+        anti_kt_f, cluster::naive::ClusterNaive, ee_gen_kt_f, ee_kt_f,
+        test_data::*,
+    };
 
     use super::*;
 
@@ -342,4 +419,94 @@ mod tests {
             assert_eq!(naive, tree)
         }
     }
+
+    #[test]
+    fn cmp_ee_kt() {
+        log_init();
+
+        for partons in [partons_3_to_2(), partons_8_to_7(), partons_9_to_7()] {
+            let mut naive = ClusterNaive::new(partons.clone(), ee_kt_f());
+            let mut tree = ClusterGeom::new(partons, ee_kt_f());
+
+            loop {
+                assert_eq!(naive.next_dist(), tree.next_dist());
+                let Some(naive_step) = naive.next() else {
+                    break;
+                };
+                assert_eq!(Some(naive_step), tree.next());
+                let mut naive_left = naive.pseudojets();
+                let mut tree_left = tree.pseudojets();
+                naive_left.sort();
+                tree_left.sort();
+                assert_eq!(naive_left, tree_left);
+            }
+            assert_eq!(tree.next(), None);
+        }
+    }
+
+    #[test]
+    fn cmp_ee_gen_kt() {
+        log_init();
+
+        for partons in [partons_4_to_4(), partons_8_to_7(), partons_9_to_7()] {
+            for p in [-1., 1.] {
+                let naive =
+                    ClusterNaive::new(partons.clone(), ee_gen_kt_f(0.4, p));
+                let tree =
+                    ClusterGeom::new(partons.clone(), ee_gen_kt_f(0.4, p));
+
+                for (naive, tree) in naive.zip(tree) {
+                    assert_eq!(naive, tree)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cmp_indexed() {
+        log_init();
+
+        let partons = partons_9_to_7();
+        let mut naive = ClusterNaive::new(partons.clone(), anti_kt_f(0.4));
+        let mut tree = ClusterGeom::new(partons, anti_kt_f(0.4));
+
+        while let Some(mut naive_step) = naive.next_indexed() {
+            let mut tree_step = tree.next_indexed().unwrap();
+            assert_eq!(naive_step.step, tree_step.step);
+            // the order of combined pseudojets is arbitrary
+            naive_step.idx.sort();
+            tree_step.idx.sort();
+            assert_eq!(naive_step.idx, tree_step.idx);
+        }
+        assert_eq!(tree.next_indexed(), None);
+    }
+
+    #[test]
+    fn cmp_recombiners() {
+        use crate::recombine::*;
+
+        log_init();
+
+        fn cmp<R: Recombiner + Copy>(recombiner: R) {
+            let partons = partons_9_to_7();
+            let naive = ClusterNaive::with_recombiner(
+                partons.clone(),
+                anti_kt_f(0.4),
+                recombiner,
+            );
+            let tree = ClusterGeom::with_recombiner(
+                partons,
+                anti_kt_f(0.4),
+                recombiner,
+            );
+
+            for (naive, tree) in naive.zip(tree) {
+                assert_eq!(naive, tree)
+            }
+        }
+        cmp(PtScheme);
+        cmp(Pt2Scheme);
+        cmp(EtScheme);
+        cmp(WtaPtScheme);
+    }
 }
@@ -1,5 +1,5 @@
 // TODO: huge amount of code duplication with `geom`
-use std::{cmp::min, f64::consts::PI};
+use std::{cmp::min, collections::BTreeSet, f64::consts::PI};
 
 use indexmap::IndexSet;
 use itertools::Itertools;
@@ -7,31 +7,136 @@ use log::{debug, trace};
 use noisy_float::prelude::*;
 use num_traits::cast::ToPrimitive;
 
-use crate::{distance::Distance, ClusterStep, PseudoJet};
+use crate::{
+    cluster::{
+        sealed::Sealed, ClusterHistExt, ClusterStepWithDist, IndexedClusterStep,
+    },
+    distance::{Distance, Geometry},
+    recombine::{EScheme, Recombiner},
+    ClusterStep, PseudoJet,
+};
+
+// Minimum tile size in rapidity and azimuthal angle
+const MIN_TILE_SIZE: f64 = 0.1;
+// Maximum absolute rapidity covered by the tiles
+const MAX_TILED_RAP: f64 = 10.;
+
+// Division of the rapidity-azimuth plane into tiles of equal size
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub(crate) struct Tiling {
+    pub(crate) max_rap: N64,
+    pub(crate) n_rap_bins: usize,
+    pub(crate) n_phi_bins: usize,
+}
+
+impl Tiling {
+    // Tiling of the rapidity range `-max_rap < y < max_rap` with as
+    // many tiles as possible, such that each tile is at least
+    // `min_size` wide in both rapidity and azimuthal angle
+    pub(crate) fn with_min_size(max_rap: N64, min_size: f64) -> Self {
+        let nbins = |range: f64| ((range / min_size).floor() as usize).max(1);
+        Self {
+            max_rap,
+            n_rap_bins: nbins(2. * f64::from(max_rap)),
+            n_phi_bins: nbins(2. * PI),
+        }
+    }
 
-const MAX_RAP: f64 = 5.;
-const N_RAP_BINS: usize = 10;
-const N_PHI_BINS: usize = 6;
+    // Rapidity and azimuth index of the tile containing `pseudojet`
+    //
+    // Pseudojets outside the rapidity range are assigned to the
+    // outermost tiles.
+    pub(crate) fn tile_coord(&self, pseudojet: &PseudoJet) -> [usize; 2] {
+        let max_rap = f64::from(self.max_rap);
+        let rap_bin_size = 2. * max_rap / self.n_rap_bins as f64;
+        let rap_coord = ((f64::from(pseudojet.rap()) + max_rap) / rap_bin_size)
+            .floor() as i32;
+        let rap_coord = rap_coord.clamp(0, self.n_rap_bins as i32 - 1) as usize;
+        let phi_coord = pseudojet.phi() * (self.n_phi_bins as f64 / (2. * PI));
+        assert!(phi_coord >= 0.);
+        // φ just below 2π can end up in the upper boundary bin due to
+        // rounding errors
+        let phi_coord = min(phi_coord.to_usize().unwrap(), self.n_phi_bins - 1);
+        [rap_coord, phi_coord]
+    }
+}
 
 /// Cluster history using the geometric O(N^2) approach of [arXiv:0512210](https://arxiv.org/abs/hep-ph/0512210) with tiling
+///
+/// The tiles are defined in the rapidity-azimuth plane, so only
+/// distances with [Geometry::RapPhi] are supported. Nearest
+/// neighbours are only searched for in adjacent tiles, so the tiles
+/// have to be at least as large as the jet radius given by
+/// [Distance::radius]. For distances without radius, a single tile
+/// is used.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
-pub struct ClusterGeomTile<D> {
+pub struct ClusterGeomTile<D, R = EScheme> {
     pseudojets: Vec<PseudoJetWithDist>,
     distance: D,
-    tiles: [[IndexSet<usize>; N_PHI_BINS]; N_RAP_BINS],
+    recombiner: R,
+    tiling: Tiling,
+    tiles: Vec<Vec<IndexSet<usize>>>,
+    // minimum distance and id of each pseudojet, ordered by distance
+    min_dists: BTreeSet<(N64, usize)>,
+    // position in `pseudojets` for each id
+    pos: Vec<usize>,
+    next_id: usize,
 }
 
 impl<D: Distance> ClusterGeomTile<D> {
     /// Initialise clustering for the given `partons` and `distance`
     pub fn new(partons: Vec<PseudoJet>, distance: D) -> Self {
+        Self::with_recombiner(partons, distance, EScheme)
+    }
+}
+
+impl<D: Distance, R: Recombiner> ClusterGeomTile<D, R> {
+    /// Initialise clustering for the given `partons`, `distance`, and
+    /// `recombiner`
+    ///
+    /// Panics if `distance` does not use the rapidity-azimuth
+    /// geometry.
+    pub fn with_recombiner(
+        partons: Vec<PseudoJet>,
+        distance: D,
+        recombiner: R,
+    ) -> Self {
+        assert_eq!(distance.geometry(), Geometry::RapPhi);
+        let tile_size = distance
+            .radius()
+            .map_or(f64::INFINITY, |r| f64::from(r).max(MIN_TILE_SIZE));
+        // pseudojets outside the rapidity range end up in the
+        // outermost tiles, which is fine as long as there aren't too
+        // many of them
+        let max_rap = partons
+            .iter()
+            .map(|p| p.rap().abs())
+            .max()
+            .unwrap_or_default()
+            .max(n64(MIN_TILE_SIZE))
+            .min(n64(MAX_TILED_RAP));
+        let tiling = Tiling::with_min_size(max_rap, tile_size);
+        debug!("using {} x {} tiles", tiling.n_rap_bins, tiling.n_phi_bins);
+        let next_id = partons.len();
         let pseudojets = partons
             .into_iter()
-            .map(|pseudojet| PseudoJetWithDist::new(pseudojet, &distance))
+            .enumerate()
+            .map(|(id, pseudojet)| {
+                PseudoJetWithDist::new(pseudojet, id, &distance)
+            })
             .collect();
         let mut res = Self {
             pseudojets,
             distance,
-            tiles: Default::default(),
+            recombiner,
+            tiling,
+            tiles: vec![
+                vec![IndexSet::new(); tiling.n_phi_bins];
+                tiling.n_rap_bins
+            ],
+            min_dists: BTreeSet::new(),
+            pos: Vec::from_iter(0..next_id),
+            next_id,
         };
         res.init_tiles();
         res.init_nearest();
@@ -39,11 +144,17 @@ impl<D: Distance> ClusterGeomTile<D> {
     }
 
     fn min_idx(&self) -> Option<usize> {
-        self.pseudojets
-            .iter()
-            .enumerate()
-            .min_by(|(_, a), (_, b)| a.cmp(b))
-            .map(|(n, _)| n)
+        let &(_, id) = self.min_dists.first()?;
+        Some(self.pos[id])
+    }
+
+    // Set the distance to the nearest neighbour for the pseudojet at
+    // `pos`, keeping the ordered minimum distances up to date
+    fn set_nearest_dist(&mut self, pos: usize, dist: N64) {
+        let pseudojet = &mut self.pseudojets[pos];
+        self.min_dists.remove(&(pseudojet.min_dist(), pseudojet.id));
+        pseudojet.nearest_dist = dist;
+        self.min_dists.insert((pseudojet.min_dist(), pseudojet.id));
     }
 
     // Exchange two pseudojets
@@ -97,6 +208,8 @@ impl<D: Distance> ClusterGeomTile<D> {
             self.tiles[rap_idx_j][phi_idx_j].insert(i);
 
             self.pseudojets.swap(i, j);
+            self.pos[self.pseudojets[i].id] = i;
+            self.pos[self.pseudojets[j].id] = j;
         }
     }
 
@@ -112,6 +225,8 @@ impl<D: Distance> ClusterGeomTile<D> {
             .tile_coord(&self.pseudojets[self.pseudojets.len() - 1].pseudojet);
         self.tiles[rap_idx][phi_idx].swap_remove(&(self.pseudojets.len() - 1));
         let pseudojet = self.pseudojets.pop().unwrap();
+        self.min_dists.remove(&(pseudojet.min_dist(), pseudojet.id));
+        self.pos[pseudojet.id] = usize::MAX;
         // TODO: maybe don't recalculate nearest neighbours yet
         self.update_nearest(&pseudojet.nearest_neighbour_for);
         trace!("After removal: {:#?}", self.pseudojets);
@@ -146,30 +261,44 @@ impl<D: Distance> ClusterGeomTile<D> {
         if nearest_idx < usize::MAX {
             assert!(nearest_idx < self.pseudojets.len());
             self.pseudojets[nearest_idx].nearest_neighbour_for.push(pos);
-            self.pseudojets[pos].nearest_dist = self
+            let dist = self
                 .distance(&self.pseudojets[pos], &self.pseudojets[nearest_idx]);
+            self.set_nearest_dist(pos, dist);
         } else {
-            self.pseudojets[pos].nearest_dist = N64::max_value()
+            self.set_nearest_dist(pos, N64::max_value())
         }
     }
 
     fn push(&mut self, pseudojet: PseudoJet) {
         trace!("before push: {:#?}", self.pseudojets);
         let [rap_idx, phi_idx] = self.tile_coord(&pseudojet);
-        let mut pseudojet = PseudoJetWithDist::new(pseudojet, &self.distance);
-        let mut nearest_dist = N64::max_value();
+        let mut pseudojet =
+            PseudoJetWithDist::new(pseudojet, self.next_id, &self.distance);
+        // as in `init_nearest`, nearest neighbours are determined
+        // from the geometric distance; using the actual distance
+        // instead would make e.g. all nearby ghosts point to a hard
+        // anti-kt jet, which then has to update all of them whenever
+        // it absorbs another ghost
+        let mut nearest_gdist = N64::max_value();
         let mut nearest_idx = usize::MAX;
         let neighbours = Vec::from_iter(self.tile_neighbours(rap_idx, phi_idx));
         for n in neighbours {
-            let d = self.distance(&pseudojet, &self.pseudojets[n]);
-            if d < nearest_dist {
-                nearest_dist = d;
+            let gdist = pseudojet.delta_r2(&self.pseudojets[n]);
+            if gdist < nearest_gdist {
+                nearest_gdist = gdist;
                 nearest_idx = n;
             }
-            if d < self.pseudojets[n].nearest_dist {
+            let current_nearest = self.pseudojets[n].nearest_neighbour_idx;
+            let is_nearer = current_nearest >= self.pseudojets.len()
+                || gdist
+                    < self.pseudojets[n]
+                        .delta_r2(&self.pseudojets[current_nearest]);
+            if is_nearer {
                 self.remove_nearest_link(n);
                 self.pseudojets[n].nearest_neighbour_idx =
                     self.pseudojets.len();
+                let dist = self.distance(&pseudojet, &self.pseudojets[n]);
+                self.set_nearest_dist(n, dist);
                 pseudojet.nearest_neighbour_for.push(n);
             }
         }
@@ -182,6 +311,9 @@ impl<D: Distance> ClusterGeomTile<D> {
                 self.distance(&pseudojet, &self.pseudojets[nearest_idx])
         }
         self.tiles[rap_idx][phi_idx].insert(self.pseudojets.len());
+        debug_assert_eq!(self.pos.len(), pseudojet.id);
+        self.pos.push(self.pseudojets.len());
+        self.min_dists.insert((pseudojet.min_dist(), pseudojet.id));
         self.pseudojets.push(pseudojet);
         trace!("after push: {:#?}", self.pseudojets);
     }
@@ -211,17 +343,12 @@ impl<D: Distance> ClusterGeomTile<D> {
         for (n, p) in self.pseudojets.iter().enumerate() {
             let [rap, phi] = self.tile_coord(&p.pseudojet);
             self.tiles[rap][phi].insert(n);
+            self.min_dists.insert((p.min_dist(), p.id));
         }
     }
 
     fn tile_coord(&self, pseudojet: &PseudoJet) -> [usize; 2] {
-        let rap_coord = (f64::from(pseudojet.rap()) + MAX_RAP).floor() as i32;
-        let rap_coord = rap_coord.clamp(0, N_RAP_BINS as i32 - 1) as usize;
-        let phi_coord = pseudojet.phi() * (N_PHI_BINS as f64 / (2. * PI));
-        assert!(phi_coord >= 0.);
-        assert!(phi_coord < N_PHI_BINS as f64);
-        let phi_coord = phi_coord.to_usize().unwrap();
-        [rap_coord, phi_coord]
+        self.tiling.tile_coord(pseudojet)
     }
 
     fn tile_neighbours(
@@ -229,16 +356,21 @@ impl<D: Distance> ClusterGeomTile<D> {
         rap_idx: usize,
         phi_idx: usize,
     ) -> impl Iterator<Item = usize> + '_ {
-        let rap_idx_range = match rap_idx + 1 {
-            1 => 0..2,
-            N_RAP_BINS => (N_RAP_BINS - 2)..N_RAP_BINS,
-            _ => (rap_idx - 1)..(rap_idx + 2),
-        };
-        let phi_idx_range = match phi_idx + 1 {
-            1 => [N_PHI_BINS - 1, 0, 1],
-            N_PHI_BINS => [N_PHI_BINS - 2, N_PHI_BINS - 1, 0],
-            _ => [phi_idx - 1, phi_idx, phi_idx + 1],
+        let n_rap_bins = self.tiling.n_rap_bins;
+        let n_phi_bins = self.tiling.n_phi_bins;
+        let rap_idx_range =
+            rap_idx.saturating_sub(1)..min(rap_idx + 2, n_rap_bins);
+        // with fewer than three tiles in φ all of them are adjacent
+        let phi_idx_range = if n_phi_bins >= 3 {
+            [
+                (phi_idx + n_phi_bins - 1) % n_phi_bins,
+                phi_idx,
+                (phi_idx + 1) % n_phi_bins,
+            ]
+        } else {
+            [0, 1, 2]
         };
+        let phi_idx_range = phi_idx_range.into_iter().take(n_phi_bins);
         rap_idx_range
             .cartesian_product(phi_idx_range)
             .flat_map(|(rap, phi)| self.tiles[rap][phi].iter().copied())
@@ -260,43 +392,68 @@ impl<D: Distance> ClusterGeomTile<D> {
             if let Some((_, nearest_idx)) = nearest {
                 assert!(nearest_idx < self.pseudojets.len());
                 self.pseudojets[i].nearest_neighbour_idx = nearest_idx;
-                self.pseudojets[i].nearest_dist = self.distance(
+                let dist = self.distance(
                     &self.pseudojets[i],
                     &self.pseudojets[nearest_idx],
                 );
+                self.set_nearest_dist(i, dist);
                 self.pseudojets[nearest_idx].nearest_neighbour_for.push(i);
             }
         }
     }
 }
 
-impl<D: Distance> Iterator for ClusterGeomTile<D> {
+impl<D: Distance, R: Recombiner> Iterator for ClusterGeomTile<D, R> {
     type Item = ClusterStep;
 
     /// Perform the next clustering step
     fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_dist().map(|s| s.step)
+    }
+}
+
+impl<D, R> Sealed for ClusterGeomTile<D, R> {}
+
+impl<D: Distance, R: Recombiner> ClusterHistExt for ClusterGeomTile<D, R> {
+    fn pseudojets(&self) -> Vec<PseudoJet> {
+        Vec::from_iter(self.pseudojets.iter().map(|p| p.pseudojet))
+    }
+
+    fn next_dist(&self) -> Option<N64> {
+        let i = self.min_idx()?;
+        Some(self.pseudojets[i].step_dist())
+    }
+
+    fn next_indexed(&mut self) -> Option<IndexedClusterStep> {
         trace!("pseudojets: {:#?}", self.pseudojets);
         let i = self.min_idx()?;
         let pi = self.remove(i);
-        if pi.beam_dist < pi.nearest_dist {
+        let dist = pi.step_dist();
+        let (step, idx) = if pi.is_jet() {
+            let id = pi.id;
             let pi = pi.pseudojet;
             debug!("new jet: {pi:?}");
-            Some(pi.into())
+            (pi.into(), [id, id])
         } else {
             let j = pi.nearest_neighbour_idx;
             debug!("cluster pseudojets {i} {j}");
             let pj = self.remove(j);
+            let ids = [pi.id, pj.id];
             let pi = pi.pseudojet;
             let pj = pj.pseudojet;
-            self.push(pi + pj);
-            Some([pi, pj].into())
-        }
+            self.push(self.recombiner.recombine(&pi, &pj));
+            self.next_id += 1;
+            ([pi, pj].into(), ids)
+        };
+        let step = ClusterStepWithDist { step, dist };
+        Some(IndexedClusterStep { step, idx })
     }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 struct PseudoJetWithDist {
     pseudojet: PseudoJet,
+    id: usize,
     beam_dist: N64,
     nearest_dist: N64,
     nearest_neighbour_idx: usize,
@@ -307,6 +464,7 @@ impl Default for PseudoJetWithDist {
     fn default() -> Self {
         Self {
             pseudojet: Default::default(),
+            id: Default::default(),
             beam_dist: N64::max_value(),
             nearest_dist: N64::max_value(),
             nearest_neighbour_idx: usize::MAX,
@@ -316,10 +474,11 @@ impl Default for PseudoJetWithDist {
 }
 
 impl PseudoJetWithDist {
-    fn new<D: Distance>(pseudojet: PseudoJet, distance: D) -> Self {
+    fn new<D: Distance>(pseudojet: PseudoJet, id: usize, distance: D) -> Self {
         Self {
             beam_dist: distance.beam_distance(&pseudojet),
             pseudojet,
+            id,
             ..Default::default()
         }
     }
@@ -328,6 +487,24 @@ impl PseudoJetWithDist {
         min(self.nearest_dist, self.beam_dist)
     }
 
+    // whether the next clustering step involving this pseudojet
+    // turns it into a jet
+    fn is_jet(&self) -> bool {
+        // without a beam distance the last pseudojet has no
+        // neighbour left to be combined with
+        self.nearest_neighbour_idx == usize::MAX
+            || self.beam_dist < self.nearest_dist
+    }
+
+    // distance of the next clustering step involving this pseudojet
+    fn step_dist(&self) -> N64 {
+        if self.is_jet() {
+            self.beam_dist
+        } else {
+            self.nearest_dist
+        }
+    }
+
     fn delta_r2(&self, p: &PseudoJetWithDist) -> N64 {
         self.pseudojet.delta_r2(&p.pseudojet)
     }
@@ -347,7 +524,10 @@ impl Ord for PseudoJetWithDist {
 
 #[cfg(test)]
 mod tests {
-    use crate::{anti_kt_f, cluster::naive::ClusterNaive, test_data::*};
+    use crate::{
+        anti_kt_f, cambridge_aachen_f, cluster::naive::ClusterNaive, ee_kt_f,
+        kt_f, test_data::*,
+    };
 
     use super::*;
 
@@ -419,4 +599,100 @@ mod tests {
             assert_eq!(naive, tree)
         }
     }
+
+    #[test]
+    fn cmp_indexed() {
+        log_init();
+
+        let partons = partons_9_to_7();
+        let mut naive = ClusterNaive::new(partons.clone(), anti_kt_f(0.4));
+        let mut tree = ClusterGeomTile::new(partons, anti_kt_f(0.4));
+
+        while let Some(mut naive_step) = naive.next_indexed() {
+            let mut tree_step = tree.next_indexed().unwrap();
+            assert_eq!(naive_step.step, tree_step.step);
+            // the order of combined pseudojets is arbitrary
+            naive_step.idx.sort();
+            tree_step.idx.sort();
+            assert_eq!(naive_step.idx, tree_step.idx);
+        }
+        assert_eq!(tree.next_indexed(), None);
+    }
+
+    #[test]
+    fn cmp_recombiners() {
+        use crate::recombine::*;
+
+        log_init();
+
+        fn cmp<R: Recombiner + Copy>(recombiner: R) {
+            let partons = partons_9_to_7();
+            let naive = ClusterNaive::with_recombiner(
+                partons.clone(),
+                anti_kt_f(0.4),
+                recombiner,
+            );
+            let tree = ClusterGeomTile::with_recombiner(
+                partons,
+                anti_kt_f(0.4),
+                recombiner,
+            );
+
+            for (naive, tree) in naive.zip(tree) {
+                assert_eq!(naive, tree)
+            }
+        }
+        cmp(PtScheme);
+        cmp(Pt2Scheme);
+        cmp(EtScheme);
+        cmp(WtaPtScheme);
+    }
+
+    #[test]
+    fn cmp_radius() {
+        log_init();
+
+        // spread out over many tiles, including some at very large
+        // rapidities
+        let mut partons = Vec::from_iter((0..200).map(|i| {
+            PseudoJet::from_pt_y_phi_m(
+                n64(1. + (37 * i % 211) as f64),
+                n64((53 * i % 97) as f64 / 97. * 8. - 4.),
+                n64((71 * i % 89) as f64 / 89. * 2. * PI),
+                n64(0.),
+            )
+        }));
+        partons.push(PseudoJet::from_pt_y_phi_m(
+            n64(0.5),
+            n64(20.),
+            n64(1.),
+            n64(0.),
+        ));
+        for r in [0.05, 0.4, 1., 3.] {
+            // the order of the jets is arbitrary for Cambridge/Aachen
+            fn cmp<D: Distance + Copy>(partons: &[PseudoJet], distance: D) {
+                let jets = |steps: Vec<ClusterStep>| {
+                    let mut jets = Vec::from_iter(
+                        steps.into_iter().filter_map(|s| match s {
+                            ClusterStep::Jet(jet) => Some(jet),
+                            ClusterStep::Combine(_) => None,
+                        }),
+                    );
+                    jets.sort_by_key(|j| j.pt());
+                    jets
+                };
+                let naive = ClusterNaive::new(partons.to_vec(), distance);
+                let tree = ClusterGeomTile::new(partons.to_vec(), distance);
+                assert_eq!(jets(naive.collect()), jets(tree.collect()));
+            }
+            cmp(&partons, &anti_kt_f(r));
+            cmp(&partons, &kt_f(r));
+            cmp(&partons, &cambridge_aachen_f(r));
+        }
+    }
+    #[test]
+    #[should_panic]
+    fn tst_angle_geometry() {
+        ClusterGeomTile::new(partons_3_to_2(), ee_kt_f());
+    }
 }
@@ -15,7 +15,11 @@
 //!
 //! * [ClusterGeomTile](crate::cluster::geom_tile::ClusterGeomTile):
 //!   the fastest implemented algorithm for a large number of partons
-//!   starting at about 50.
+//!   starting at about 50. Since the tiles are defined in the
+//!   rapidity-azimuth plane and sized according to the jet radius,
+//!   this algorithm is not used for distances based on opening
+//!   angles, like the ones for lepton collisions, or for distances
+//!   without a [radius](crate::distance::Distance::radius).
 //!
 /// Clustering using the geometric O(N^2) approach of [arXiv:0512210](https://arxiv.org/abs/hep-ph/0512210)
 pub mod geom;
@@ -23,14 +27,22 @@ pub mod geom;
 pub mod geom_tile;
 /// Naive clustering
 pub mod naive;
+/// Cluster sequences with the full clustering history
+pub mod sequence;
 
-use crate::distance::Distance;
-use crate::pseudojet::PseudoJet;
+use crate::area::{self, GhostSpec, JetWithArea};
+use crate::distance::{Distance, Geometry};
+use crate::error::Error;
+use crate::pseudojet::{PseudoJet, TryIntoPseudoJet};
+use crate::recombine::{EScheme, Recombiner};
 
-use std::cmp::Ord;
+use std::cmp::{max, Ord};
 use std::hash::Hash;
 
 use log::debug;
+use noisy_float::{prelude::Float, types::N64};
+
+pub use self::sequence::{ClusterSequence, HistoryEntry};
 
 use self::{
     geom::ClusterGeom, geom_tile::ClusterGeomTile, naive::ClusterNaive,
@@ -110,6 +122,170 @@ where
     }
 }
 
+// Prevents implementations of the extension traits outside this crate
+mod sealed {
+    pub trait Sealed {}
+}
+
+impl sealed::Sealed for Vec<PseudoJet> {}
+impl<T> sealed::Sealed for &[T] {}
+
+/// Additional clustering methods for the inputs supported by [Cluster]
+///
+/// This trait cannot be implemented outside this crate.
+pub trait ClusterExt: Cluster + sealed::Sealed {
+    /// Cluster into jets using the distance measure `d` and
+    /// determine their active areas with the ghosts defined by
+    /// `ghosts`
+    ///
+    /// See [area::cluster_with_active_area] for details.
+    fn cluster_with_area<D: Distance>(
+        self,
+        d: D,
+        ghosts: &GhostSpec,
+    ) -> Vec<JetWithArea>;
+
+    /// Cluster into exactly `n` jets using the distance measure `d`
+    ///
+    /// See [ClusterHistory::exclusive_n] for details.
+    fn cluster_exclusive_n<D: Distance>(self, d: D, n: usize)
+        -> Vec<PseudoJet>;
+
+    /// Cluster into jets using the distance measure `d` until all
+    /// distances are above `dcut`
+    ///
+    /// See [ClusterHistory::exclusive_dcut] for details.
+    fn cluster_exclusive_dcut<D: Distance>(
+        self,
+        d: D,
+        dcut: N64,
+    ) -> Vec<PseudoJet>;
+
+    /// Differential jet rates y_{n,n+1} for the distance measure `d`
+    /// and the squared hard scale `q2`
+    ///
+    /// See [ClusterHistory::diff_jet_rates] for details.
+    fn diff_jet_rates<D: Distance>(self, d: D, q2: N64) -> Vec<N64>;
+}
+
+impl ClusterExt for Vec<PseudoJet> {
+    fn cluster_with_area<D: Distance>(
+        self,
+        d: D,
+        ghosts: &GhostSpec,
+    ) -> Vec<JetWithArea> {
+        debug!("clustering partons with ghosts: {self:#?}");
+        area::cluster_with_active_area(self, d, ghosts)
+    }
+
+    fn cluster_exclusive_n<D: Distance>(
+        self,
+        d: D,
+        n: usize,
+    ) -> Vec<PseudoJet> {
+        debug!("exclusively clustering partons: {self:#?}");
+        ClusterHistory::new(self, d).exclusive_n(n)
+    }
+
+    fn cluster_exclusive_dcut<D: Distance>(
+        self,
+        d: D,
+        dcut: N64,
+    ) -> Vec<PseudoJet> {
+        debug!("exclusively clustering partons: {self:#?}");
+        ClusterHistory::new(self, d).exclusive_dcut(dcut)
+    }
+
+    fn diff_jet_rates<D: Distance>(self, d: D, q2: N64) -> Vec<N64> {
+        debug!("calculating jet rates for partons: {self:#?}");
+        ClusterHistory::new(self, d).diff_jet_rates(q2)
+    }
+}
+
+impl<'a, T> ClusterExt for &'a [T]
+where
+    &'a T: Into<PseudoJet>,
+{
+    fn cluster_with_area<D: Distance>(
+        self,
+        d: D,
+        ghosts: &GhostSpec,
+    ) -> Vec<JetWithArea> {
+        let partons = Vec::from_iter(self.iter().map(|p| p.into()));
+        partons.cluster_with_area(d, ghosts)
+    }
+
+    fn cluster_exclusive_n<D: Distance>(
+        self,
+        d: D,
+        n: usize,
+    ) -> Vec<PseudoJet> {
+        let partons = Vec::from_iter(self.iter().map(|p| p.into()));
+        partons.cluster_exclusive_n(d, n)
+    }
+
+    fn cluster_exclusive_dcut<D: Distance>(
+        self,
+        d: D,
+        dcut: N64,
+    ) -> Vec<PseudoJet> {
+        let partons = Vec::from_iter(self.iter().map(|p| p.into()));
+        partons.cluster_exclusive_dcut(d, dcut)
+    }
+
+    fn diff_jet_rates<D: Distance>(self, d: D, q2: N64) -> Vec<N64> {
+        let partons = Vec::from_iter(self.iter().map(|p| p.into()));
+        partons.diff_jet_rates(d, q2)
+    }
+}
+
+/// Clustering with validation for the inputs supported by [Cluster]
+///
+/// This trait cannot be implemented outside this crate.
+pub trait TryClusterExt: Cluster + sealed::Sealed {
+    /// Cluster into jets using the distance measure `d` after
+    /// checking that all inputs are physical
+    ///
+    /// Returns [Error::InvalidParticle] with the index of the first
+    /// input failing the check in [PseudoJet::check].
+    fn try_cluster<D: Distance>(self, d: D) -> Result<Vec<PseudoJet>, Error>;
+}
+
+impl TryClusterExt for Vec<PseudoJet> {
+    fn try_cluster<D: Distance>(self, d: D) -> Result<Vec<PseudoJet>, Error> {
+        for (index, p) in self.iter().enumerate() {
+            p.check().map_err(|err| Error::InvalidParticle {
+                index,
+                source: Box::new(err),
+            })?;
+        }
+        Ok(self.cluster(d))
+    }
+}
+
+impl<'a, T> TryClusterExt for &'a [T]
+where
+    &'a T: Into<PseudoJet>,
+    T: TryIntoPseudoJet,
+{
+    fn try_cluster<D: Distance>(self, d: D) -> Result<Vec<PseudoJet>, Error> {
+        // validate before converting, since the conversion itself
+        // can panic for NaN input
+        let partons: Vec<_> = self
+            .iter()
+            .enumerate()
+            .map(|(index, p)| {
+                p.try_into_pseudojet()
+                    .map_err(|err| Error::InvalidParticle {
+                        index,
+                        source: Box::new(err),
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(partons.cluster(d))
+    }
+}
+
 /// Result of a clustering step
 #[derive(Clone, Debug, Ord, PartialOrd)]
 pub enum ClusterStep {
@@ -161,13 +337,79 @@ impl Hash for ClusterStep {
     }
 }
 
+/// Result of a clustering step together with the distance at which
+/// it happened
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ClusterStepWithDist {
+    /// Result of the clustering step
+    pub step: ClusterStep,
+    /// Distance of the clustering step
+    ///
+    /// For [ClusterStep::Combine] this is the distance d_ij between
+    /// the combined pseudojets, for [ClusterStep::Jet] the beam
+    /// distance d_iB of the jet. Without a beam distance, the last
+    /// jet has an infinite distance.
+    pub dist: N64,
+}
+
+impl ClusterStepWithDist {
+    /// Whether [dist](Self::dist) is a beam distance d_iB
+    ///
+    /// Otherwise, it is the distance d_ij between two pseudojets.
+    pub fn is_beam_dist(&self) -> bool {
+        matches!(self.step, ClusterStep::Jet(_))
+    }
+}
+
+/// Result of a clustering step together with its distance and the
+/// history indices of the involved pseudojets
+///
+/// The input partons have the history indices 0, ..., n-1 in their
+/// original order. Each time two pseudojets are combined, the new
+/// pseudojet is assigned the next free index, starting at n.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct IndexedClusterStep {
+    /// Result of the clustering step with its distance
+    pub step: ClusterStepWithDist,
+    /// History indices of the involved pseudojets
+    ///
+    /// For [ClusterStep::Combine] these are the indices of the two
+    /// combined pseudojets in the same order. For [ClusterStep::Jet]
+    /// both entries are the index of the jet.
+    pub idx: [usize; 2],
+}
+
 /// Trait marking a clustering algorithm
 pub trait ClusterHist: Iterator<Item = ClusterStep> {}
 
 impl<T> ClusterHist for T where T: Iterator<Item = ClusterStep> {}
 
+/// Additional methods of the clustering algorithms implemented in
+/// this crate
+///
+/// This trait cannot be implemented outside this crate.
+pub trait ClusterHistExt: ClusterHist + sealed::Sealed {
+    /// Pseudojets that have been neither combined nor turned into jets yet
+    fn pseudojets(&self) -> Vec<PseudoJet>;
+
+    /// Distance at which the next clustering step will happen
+    fn next_dist(&self) -> Option<N64>;
+
+    /// Perform the next clustering step and also return its distance
+    fn next_with_dist(&mut self) -> Option<ClusterStepWithDist> {
+        self.next_indexed().map(|s| s.step)
+    }
+
+    /// Perform the next clustering step and also return its distance
+    /// and the history indices of the involved pseudojets
+    fn next_indexed(&mut self) -> Option<IndexedClusterStep>;
+}
+
 /// General-purpose cluster history
-pub struct ClusterHistory<'a>(Box<dyn ClusterHist + 'a>);
+pub struct ClusterHistory<'a> {
+    hist: Box<dyn ClusterHistExt + 'a>,
+    npseudojets: usize,
+}
 
 impl<'a> ClusterHistory<'a> {
     const START_GEOM_THRESHOLD: usize = 25;
@@ -176,16 +418,115 @@ impl<'a> ClusterHistory<'a> {
 
     /// Initialise clustering for the given `partons` and `distance`
     pub fn new<D: Distance + 'a>(partons: Vec<PseudoJet>, distance: D) -> Self {
-        let hist: Box<dyn ClusterHist> = match partons.len() {
-            Self::START_TILE_THRESHOLD.. => {
-                Box::new(ClusterGeomTile::new(partons, distance))
-            }
-            Self::START_GEOM_THRESHOLD..=Self::END_GEOM_THRESHOLD => {
-                Box::new(ClusterGeom::new(partons, distance))
-            }
-            _ => Box::new(ClusterNaive::new(partons, distance)),
+        Self::with_recombiner(partons, distance, EScheme)
+    }
+
+    /// Initialise clustering for the given `partons`, `distance`, and
+    /// `recombiner`
+    pub fn with_recombiner<D, R>(
+        partons: Vec<PseudoJet>,
+        distance: D,
+        recombiner: R,
+    ) -> Self
+    where
+        D: Distance + 'a,
+        R: Recombiner + 'a,
+    {
+        let npseudojets = partons.len();
+        let tileable = distance.geometry() == Geometry::RapPhi
+            && distance.radius().is_some();
+        let hist: Box<dyn ClusterHistExt> = match partons.len() {
+            Self::START_TILE_THRESHOLD.. if tileable => Box::new(
+                ClusterGeomTile::with_recombiner(partons, distance, recombiner),
+            ),
+            Self::START_GEOM_THRESHOLD.. => Box::new(
+                ClusterGeom::with_recombiner(partons, distance, recombiner),
+            ),
+            _ => Box::new(ClusterNaive::with_recombiner(
+                partons, distance, recombiner,
+            )),
         };
-        Self(hist)
+        Self { hist, npseudojets }
+    }
+
+    /// Pseudojets that have been neither combined nor turned into jets yet
+    pub fn pseudojets(&self) -> Vec<PseudoJet> {
+        self.hist.pseudojets()
+    }
+
+    /// Continue clustering until exactly `n` pseudojets are left and
+    /// return them
+    ///
+    /// Each clustering step reduces the number of pseudojets by
+    /// one. For distance measures with a beam distance, this means
+    /// that pseudojets that would become inclusive jets are instead
+    /// merged with the beam and discarded. If there are at most `n`
+    /// pseudojets to begin with, all of them are returned.
+    pub fn exclusive_n(mut self, n: usize) -> Vec<PseudoJet> {
+        while self.npseudojets > n {
+            let step = self.next();
+            debug_assert!(step.is_some());
+        }
+        self.pseudojets()
+    }
+
+    /// Continue clustering until all distances are above `dcut` and
+    /// return the remaining pseudojets
+    ///
+    /// As in [exclusive_n](Self::exclusive_n), pseudojets with a beam
+    /// distance below `dcut` are merged with the beam and discarded.
+    pub fn exclusive_dcut(mut self, dcut: N64) -> Vec<PseudoJet> {
+        while self.next_dist().is_some_and(|d| d <= dcut) {
+            self.next();
+        }
+        self.pseudojets()
+    }
+
+    /// Continue clustering until no pseudojets are left and return
+    /// the differential jet rates y_{n,n+1} = d_{n,n+1}/`q2`
+    ///
+    /// The n-th entry of the result is y_{n,n+1}, where d_{n,n+1} is
+    /// the largest distance among all clustering steps up to the
+    /// one leaving n pseudojets. This means that exactly n jets are
+    /// obtained from [exclusive_dcut](Self::exclusive_dcut) for
+    /// y_{n,n+1} <= dcut/`q2` < y_{n-1,n}, unless several steps
+    /// happen at the same distance. The last step is always merging
+    /// with the beam, so for distances without beam distance y_{0,1}
+    /// is infinite.
+    ///
+    /// For the Durham algorithm `q2` is usually the square of the
+    /// total visible energy.
+    pub fn diff_jet_rates(self, q2: N64) -> Vec<N64> {
+        let mut dmax = N64::neg_infinity();
+        let mut rates = Vec::from_iter(self.with_dist().map(|step| {
+            dmax = max(dmax, step.dist);
+            dmax / q2
+        }));
+        rates.reverse();
+        rates
+    }
+
+    /// Distance at which the next clustering step will happen
+    pub fn next_dist(&self) -> Option<N64> {
+        self.hist.next_dist()
+    }
+
+    /// Perform the next clustering step and also return its distance
+    pub fn next_with_dist(&mut self) -> Option<ClusterStepWithDist> {
+        self.next_indexed().map(|s| s.step)
+    }
+
+    /// Perform the next clustering step and also return its distance
+    /// and the history indices of the involved pseudojets
+    pub fn next_indexed(&mut self) -> Option<IndexedClusterStep> {
+        let step = self.hist.next_indexed()?;
+        self.npseudojets -= 1;
+        Some(step)
+    }
+
+    /// Iterate over the clustering steps together with their distances
+    pub fn with_dist(self) -> ClusterHistoryWithDist<'a> {
+        ClusterHistoryWithDist(self)
     }
 }
 
@@ -194,6 +535,20 @@ impl Iterator for ClusterHistory<'_> {
 
     /// Perform the next clustering step
     fn next(&mut self) -> Option<Self::Item> {
-        self.0.next()
+        self.next_with_dist().map(|s| s.step)
+    }
+}
+
+/// Cluster history including the distance of each step
+///
+/// Obtained from [ClusterHistory::with_dist].
+pub struct ClusterHistoryWithDist<'a>(ClusterHistory<'a>);
+
+impl Iterator for ClusterHistoryWithDist<'_> {
+    type Item = ClusterStepWithDist;
+
+    /// Perform the next clustering step
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_with_dist()
     }
 }
@@ -1,30 +1,58 @@
 use log::{debug, trace};
 use noisy_float::types::N64;
 
-use crate::{distance::Distance, ClusterStep, PseudoJet};
+use crate::{
+    cluster::{
+        sealed::Sealed, ClusterHistExt, ClusterStepWithDist, IndexedClusterStep,
+    },
+    distance::Distance,
+    recombine::{EScheme, Recombiner},
+    ClusterStep, PseudoJet,
+};
 
 /// Cluster history using naive brute-force nearest-neighbour search
 #[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
-pub struct ClusterNaive<D> {
+pub struct ClusterNaive<D, R = EScheme> {
     pseudojets: Vec<PseudoJet>,
     distance: D,
+    recombiner: R,
     distances: Vec<(N64, usize, usize)>,
+    // history indices of the pseudojets
+    ids: Vec<usize>,
+    next_id: usize,
 }
 
 impl<D: Distance> ClusterNaive<D> {
     /// Initialise clustering for the given `partons` and `distance`
     pub fn new(partons: Vec<PseudoJet>, distance: D) -> Self {
+        Self::with_recombiner(partons, distance, EScheme)
+    }
+}
+
+impl<D: Distance, R: Recombiner> ClusterNaive<D, R> {
+    /// Initialise clustering for the given `partons`, `distance`, and
+    /// `recombiner`
+    pub fn with_recombiner(
+        partons: Vec<PseudoJet>,
+        distance: D,
+        recombiner: R,
+    ) -> Self {
         let distances = calc_distances(&partons, &distance);
+        let ids = Vec::from_iter(0..partons.len());
         Self {
+            next_id: partons.len(),
             pseudojets: partons,
             distance,
+            recombiner,
             distances,
+            ids,
         }
     }
 
-    fn extract_as_jet(&mut self, i: usize) -> PseudoJet {
+    fn extract_as_jet(&mut self, i: usize) -> (PseudoJet, usize) {
         self.distances.retain(|(_, ii, jj)| *ii != i && *jj != i);
         let jet = self.pseudojets.swap_remove(i);
+        let id = self.ids.swap_remove(i);
         debug!("new jet: {:?}", jet);
         for (_dist, ii, jj) in &mut self.distances {
             if *ii == self.pseudojets.len() {
@@ -35,16 +63,19 @@ impl<D: Distance> ClusterNaive<D> {
             }
         }
         trace!("distances: {:#?}", self.distances);
-        jet
+        (jet, id)
     }
 
-    fn combine(&mut self, i: usize, j: usize) -> [PseudoJet; 2] {
+    fn combine(&mut self, i: usize, j: usize) -> ([PseudoJet; 2], [usize; 2]) {
         let res = [self.pseudojets[i], self.pseudojets[j]];
+        let ids = [self.ids[i], self.ids[j]];
+        let combined = self.recombiner.recombine(&res[0], &res[1]);
         let (i, j) = minmax(i, j);
         debug_assert!(j > i);
         debug!("cluster pseudojets {} {}", i, j);
         self.distances.retain(|(_, ii, jj)| *ii != j && *jj != j);
-        let p2 = self.pseudojets.swap_remove(j);
+        self.pseudojets.swap_remove(j);
+        self.ids.swap_remove(j);
         for (_dist, ii, jj) in &mut self.distances {
             if *ii == self.pseudojets.len() {
                 *ii = j
@@ -53,7 +84,9 @@ impl<D: Distance> ClusterNaive<D> {
                 *jj = j
             }
         }
-        self.pseudojets[i] += p2;
+        self.pseudojets[i] = combined;
+        self.ids[i] = self.next_id;
+        self.next_id += 1;
         // update distances
         let affected_dists = self
             .distances
@@ -68,24 +101,41 @@ impl<D: Distance> ClusterNaive<D> {
             };
         }
         trace!("distances: {:#?}", self.distances);
-        res
+        (res, ids)
     }
 }
 
-impl<D: Distance> Iterator for ClusterNaive<D> {
+impl<D: Distance, R: Recombiner> Iterator for ClusterNaive<D, R> {
     type Item = ClusterStep;
 
     /// Perform the next clustering step
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(&(_, i, j)) = self.distances.iter().min() {
-            if i == j {
-                Some(self.extract_as_jet(i).into())
-            } else {
-                Some(self.combine(i, j).into())
-            }
+        self.next_with_dist().map(|s| s.step)
+    }
+}
+
+impl<D, R> Sealed for ClusterNaive<D, R> {}
+
+impl<D: Distance, R: Recombiner> ClusterHistExt for ClusterNaive<D, R> {
+    fn pseudojets(&self) -> Vec<PseudoJet> {
+        self.pseudojets.clone()
+    }
+
+    fn next_dist(&self) -> Option<N64> {
+        self.distances.iter().min().map(|(dist, _, _)| *dist)
+    }
+
+    fn next_indexed(&mut self) -> Option<IndexedClusterStep> {
+        let &(dist, i, j) = self.distances.iter().min()?;
+        let (step, idx) = if i == j {
+            let (jet, id) = self.extract_as_jet(i);
+            (jet.into(), [id, id])
         } else {
-            None
-        }
+            let (pseudojets, ids) = self.combine(i, j);
+            (pseudojets.into(), ids)
+        };
+        let step = ClusterStepWithDist { step, dist };
+        Some(IndexedClusterStep { step, idx })
     }
 }
 
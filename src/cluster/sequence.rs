@@ -0,0 +1,239 @@
+use log::debug;
+use noisy_float::prelude::*;
+
+use crate::{
+    cluster::{ClusterHistory, ClusterStep, IndexedClusterStep},
+    distance::Distance,
+    recombine::{EScheme, Recombiner},
+    PseudoJet,
+};
+
+/// Entry in the history of a [ClusterSequence]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct HistoryEntry {
+    /// The pseudojet
+    pub pseudojet: PseudoJet,
+    /// History indices of the two pseudojets that were combined into
+    /// this one
+    ///
+    /// This is `None` for input partons.
+    pub parents: Option<[usize; 2]>,
+    /// History index of the pseudojet this one was combined into
+    ///
+    /// This is `None` for jets.
+    pub child: Option<usize>,
+    /// Distance at which the pseudojet was combined with another one
+    /// or turned into a jet
+    pub dist: N64,
+}
+
+/// Complete clustering history
+///
+/// The history starts with the input partons in their original
+/// order, followed by the pseudojets obtained from combining two
+/// others in the order they were created. This means that the
+/// history index of each pseudojet agrees with the one in
+/// [IndexedClusterStep].
+///
+/// # Examples
+///
+/// ```rust
+/// use jetty::{anti_kt_f, pseudojet_f, ClusterSequence};
+///
+/// let partons = vec![
+///     pseudojet_f(0.2626773221934335, -0.08809521946454194, -0.1141608706693822, -0.2195584284654444),
+///     pseudojet_f(2.21902459329915, -0.7529973704809976, -0.9658189214109036, -1.850475321845671)
+/// ];
+///
+/// let seq = ClusterSequence::new(partons, anti_kt_f(0.4));
+/// assert_eq!(seq.jets().len(), 1);
+/// assert_eq!(seq.constituents(seq.jets()[0]), [0, 1]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ClusterSequence {
+    history: Vec<HistoryEntry>,
+    jets: Vec<usize>,
+    npartons: usize,
+}
+
+impl ClusterSequence {
+    /// Cluster the given `partons` using the distance measure `distance`
+    pub fn new<D: Distance>(partons: Vec<PseudoJet>, distance: D) -> Self {
+        Self::with_recombiner(partons, distance, EScheme)
+    }
+
+    /// Cluster the given `partons` using the distance measure
+    /// `distance` and the recombination scheme `recombiner`
+    pub fn with_recombiner<D: Distance, R: Recombiner>(
+        partons: Vec<PseudoJet>,
+        distance: D,
+        recombiner: R,
+    ) -> Self {
+        let npartons = partons.len();
+        let mut history =
+            Vec::from_iter(partons.iter().map(|&pseudojet| HistoryEntry {
+                pseudojet,
+                parents: None,
+                child: None,
+                dist: N64::infinity(),
+            }));
+        let mut jets = Vec::new();
+        let mut clustering =
+            ClusterHistory::with_recombiner(partons, distance, &recombiner);
+        while let Some(IndexedClusterStep { step, idx }) =
+            clustering.next_indexed()
+        {
+            match step.step {
+                ClusterStep::Combine([p1, p2]) => {
+                    let child = history.len();
+                    for i in idx {
+                        debug_assert!(history[i].child.is_none());
+                        history[i].child = Some(child);
+                        history[i].dist = step.dist;
+                    }
+                    history.push(HistoryEntry {
+                        pseudojet: recombiner.recombine(&p1, &p2),
+                        parents: Some(idx),
+                        child: None,
+                        dist: N64::infinity(),
+                    });
+                }
+                ClusterStep::Jet(_) => {
+                    let [i, _] = idx;
+                    history[i].dist = step.dist;
+                    jets.push(i);
+                }
+            }
+        }
+        debug!("cluster sequence: {history:#?}");
+        Self {
+            history,
+            jets,
+            npartons,
+        }
+    }
+
+    /// The clustering history
+    pub fn history(&self) -> &[HistoryEntry] {
+        &self.history
+    }
+
+    /// Number of input partons
+    ///
+    /// These are the first entries in the [history](Self::history).
+    pub fn npartons(&self) -> usize {
+        self.npartons
+    }
+
+    /// History indices of the jets in the order they were found
+    pub fn jets(&self) -> &[usize] {
+        &self.jets
+    }
+
+    /// All jets in the order they were found
+    pub fn inclusive_jets(&self) -> Vec<PseudoJet> {
+        Vec::from_iter(self.jets.iter().map(|&i| self.history[i].pseudojet))
+    }
+
+    /// Indices of the input partons that were combined into the
+    /// pseudojet with history index `idx`
+    ///
+    /// The indices are sorted in ascending order.
+    pub fn constituents(&self, idx: usize) -> Vec<usize> {
+        let mut constituents = Vec::new();
+        let mut todo = vec![idx];
+        while let Some(idx) = todo.pop() {
+            match self.history[idx].parents {
+                Some(parents) => todo.extend(parents),
+                None => constituents.push(idx),
+            }
+        }
+        constituents.sort_unstable();
+        constituents
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{anti_kt_f, ee_kt_f, test_data::*, Cluster};
+
+    use super::*;
+
+    fn log_init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    fn check_constituents(partons: Vec<PseudoJet>, seq: &ClusterSequence) {
+        let mut all_constituents = Vec::new();
+        for &jet in seq.jets() {
+            let constituents = seq.constituents(jet);
+            let sum = constituents
+                .iter()
+                .fold(PseudoJet::new(), |s, &i| s + partons[i]);
+            for i in 0..4 {
+                let diff = f64::from(sum[i] - seq.history()[jet].pseudojet[i]);
+                assert!(diff.abs() < 1e-10);
+            }
+            all_constituents.extend(constituents);
+        }
+        all_constituents.sort();
+        assert_eq!(all_constituents, Vec::from_iter(0..partons.len()));
+    }
+
+    #[test]
+    fn tst_sequence_9_to_7() {
+        log_init();
+
+        let partons = partons_9_to_7();
+        let seq = ClusterSequence::new(partons.clone(), anti_kt_f(0.4));
+        assert_eq!(seq.npartons(), partons.len());
+        assert_eq!(
+            seq.inclusive_jets(),
+            partons.clone().cluster(anti_kt_f(0.4))
+        );
+        check_constituents(partons, &seq);
+    }
+
+    #[test]
+    fn tst_sequence_ee_kt() {
+        log_init();
+
+        let partons = partons_8_to_7();
+        let seq = ClusterSequence::new(partons.clone(), ee_kt_f());
+        assert_eq!(seq.jets().len(), 1);
+        assert_eq!(seq.history().len(), 2 * partons.len() - 1);
+        for (i, entry) in seq.history().iter().enumerate() {
+            assert_eq!(entry.child.is_none(), i == seq.jets()[0]);
+            if let Some(parents) = entry.parents {
+                for parent in parents {
+                    assert_eq!(seq.history()[parent].child, Some(i));
+                }
+            }
+        }
+        check_constituents(partons, &seq);
+    }
+
+    #[test]
+    fn tst_sequence_wta() {
+        use crate::recombine::WtaPtScheme;
+
+        log_init();
+
+        let partons = partons_9_to_7();
+        let seq = ClusterSequence::with_recombiner(
+            partons.clone(),
+            anti_kt_f(0.4),
+            WtaPtScheme,
+        );
+        let history = ClusterHistory::with_recombiner(
+            partons,
+            anti_kt_f(0.4),
+            WtaPtScheme,
+        );
+        let jets = Vec::from_iter(history.filter_map(|s| match s {
+            ClusterStep::Jet(jet) => Some(jet),
+            _ => None,
+        }));
+        assert_eq!(seq.inclusive_jets(), jets);
+    }
+}
@@ -1,6 +1,7 @@
 use crate::pseudojet::PseudoJet;
 
 use std::cmp::min;
+use std::f64::consts::PI;
 
 use noisy_float::prelude::*;
 
@@ -9,7 +10,47 @@ pub trait Distance {
     /// Distance between pseudojets
     fn distance(&self, p1: &PseudoJet, p2: &PseudoJet) -> N64;
     /// Distance to the beam axis
+    ///
+    /// Distance measures without a beam distance, like the ones used
+    /// for lepton collisions, should return infinity.
     fn beam_distance(&self, p1: &PseudoJet) -> N64;
+    /// Geometry in which nearest neighbours are searched for
+    fn geometry(&self) -> Geometry {
+        Geometry::RapPhi
+    }
+    /// Radius R, such that pseudojets further apart than R in the
+    /// rapidity-azimuth plane are never combined
+    ///
+    /// This is used to choose the tile size in tiled clustering. The
+    /// default `None` disables tiling.
+    fn radius(&self) -> Option<N64> {
+        None
+    }
+}
+
+/// Geometric distance underlying a distance measure
+///
+/// The geometric clustering algorithms assume that the nearest
+/// neighbour with respect to the actual distance measure is also
+/// the nearest neighbour with respect to the geometric distance of
+/// at least one of the two pseudojets.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Geometry {
+    /// ΔR^2 = Δφ^2 + Δy^2 in the rapidity-azimuth plane
+    #[default]
+    RapPhi,
+    /// 1 - cos θ, where θ is the opening angle
+    Angle,
+}
+
+impl Geometry {
+    /// Geometric distance between pseudojets
+    pub fn distance(&self, p1: &PseudoJet, p2: &PseudoJet) -> N64 {
+        match self {
+            Self::RapPhi => p1.delta_r2(p2),
+            Self::Angle => one_minus_cos_theta(p1, p2),
+        }
+    }
 }
 
 pub struct AntiKt {
@@ -34,6 +75,10 @@ impl Distance for AntiKt {
     fn beam_distance(&self, p1: &PseudoJet) -> N64 {
         p1.inv_pt2()
     }
+
+    fn radius(&self) -> Option<N64> {
+        Some(self.r2.sqrt())
+    }
 }
 
 pub struct Kt {
@@ -58,6 +103,10 @@ impl Distance for Kt {
     fn beam_distance(&self, p1: &PseudoJet) -> N64 {
         p1.pt2()
     }
+
+    fn radius(&self) -> Option<N64> {
+        Some(self.r2.sqrt())
+    }
 }
 
 pub struct CambridgeAachen {
@@ -82,6 +131,10 @@ impl Distance for CambridgeAachen {
     fn beam_distance(&self, _p1: &PseudoJet) -> N64 {
         n64(1.)
     }
+
+    fn radius(&self) -> Option<N64> {
+        Some(self.r2.sqrt())
+    }
 }
 
 pub struct GenKt {
@@ -108,6 +161,85 @@ impl Distance for GenKt {
     fn beam_distance(&self, p1: &PseudoJet) -> N64 {
         p1.pt2().powf(self.p)
     }
+
+    fn radius(&self) -> Option<N64> {
+        Some(self.r2.sqrt())
+    }
+}
+
+pub struct EeKt;
+
+/// Durham (e+e- kt) distance measure
+///
+/// The distance between two pseudojets is 2 min(E_i^2, E_j^2) (1 - cos θ_ij).
+/// There is no beam distance, so inclusive clustering always results
+/// in a single jet.
+pub fn ee_kt() -> EeKt {
+    EeKt
+}
+
+/// Durham (e+e- kt) distance measure
+///
+/// This is the same as [ee_kt], which does not take any parameters.
+pub fn ee_kt_f() -> EeKt {
+    ee_kt()
+}
+
+impl Distance for EeKt {
+    fn distance(&self, p1: &PseudoJet, p2: &PseudoJet) -> N64 {
+        let e2 = min(p1.e() * p1.e(), p2.e() * p2.e());
+        n64(2.) * e2 * one_minus_cos_theta(p1, p2)
+    }
+
+    fn beam_distance(&self, _p1: &PseudoJet) -> N64 {
+        N64::infinity()
+    }
+
+    fn geometry(&self) -> Geometry {
+        Geometry::Angle
+    }
+}
+
+pub struct EeGenKt {
+    norm: N64,
+    p: N64,
+}
+
+/// Generalised kt distance measure for lepton collisions with radius
+/// parameter `r` and exponent `p`
+///
+/// The distance between two pseudojets is
+/// min(E_i^(2p), E_j^(2p)) (1 - cos θ_ij) / (1 - cos R) and the beam
+/// distance is E^(2p). For R > π the normalisation 1 - cos R is
+/// replaced by 3 + cos R, as in fastjet.
+pub fn ee_gen_kt(r: N64, p: N64) -> EeGenKt {
+    let norm = if r <= PI {
+        n64(1.) - r.cos()
+    } else {
+        n64(3.) + r.cos()
+    };
+    EeGenKt { norm, p }
+}
+
+/// Generalised kt distance measure for lepton collisions with radius
+/// parameter `r` and exponent `p`
+pub fn ee_gen_kt_f(r: f64, p: f64) -> EeGenKt {
+    ee_gen_kt(n64(r), n64(p))
+}
+
+impl Distance for EeGenKt {
+    fn distance(&self, p1: &PseudoJet, p2: &PseudoJet) -> N64 {
+        let e2p = min(self.beam_distance(p1), self.beam_distance(p2));
+        e2p * one_minus_cos_theta(p1, p2) / self.norm
+    }
+
+    fn beam_distance(&self, p1: &PseudoJet) -> N64 {
+        (p1.e() * p1.e()).powf(self.p)
+    }
+
+    fn geometry(&self) -> Geometry {
+        Geometry::Angle
+    }
 }
 
 impl<T: Distance> Distance for &T {
@@ -118,4 +250,24 @@ impl<T: Distance> Distance for &T {
     fn beam_distance(&self, p1: &PseudoJet) -> N64 {
         (*self).beam_distance(p1)
     }
+
+    fn geometry(&self) -> Geometry {
+        (*self).geometry()
+    }
+
+    fn radius(&self) -> Option<N64> {
+        (*self).radius()
+    }
+}
+
+// 1 - cos θ for the opening angle θ between two pseudojets
+fn one_minus_cos_theta(p1: &PseudoJet, p2: &PseudoJet) -> N64 {
+    let dot = p1.px() * p2.px() + p1.py() * p2.py() + p1.pz() * p2.pz();
+    let norm2 =
+        |p: &PseudoJet| p.px() * p.px() + p.py() * p.py() + p.pz() * p.pz();
+    let norm = (norm2(p1) * norm2(p2)).sqrt();
+    if norm == 0. {
+        return n64(1.);
+    }
+    n64(1.) - dot / norm
 }
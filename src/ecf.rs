@@ -0,0 +1,353 @@
+//! Energy correlation functions, see
+//! [arXiv:1305.0007](https://arxiv.org/abs/1305.0007) and
+//! [arXiv:1609.07483](https://arxiv.org/abs/1609.07483). With
+//! momentum fractions z_i and pairwise angles θ_ij of the
+//! constituents of a jet, the generalised energy correlation
+//! functions are
+//!
+//! _v e_n^(β) = Σ_{i_1 < ... < i_n} z_{i_1} ... z_{i_n} Π_{m=1}^v min^(m) θ^β,
+//!
+//! where min^(m) denotes the m-th smallest of the pairwise angles
+//! between the n constituents. The standard correlation functions
+//! e_2^(β) and e_3^(β) correspond to v = 1 and v = 3, respectively.
+use noisy_float::prelude::*;
+
+use crate::PseudoJet;
+
+/// Choice of momentum fractions and angles
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Measure {
+    /// Transverse momentum fractions and distances ΔR in the
+    /// rapidity-azimuth plane, as appropriate for hadron colliders
+    #[default]
+    PtR,
+    /// Energy fractions and opening angles, as appropriate for
+    /// lepton colliders
+    ETheta,
+}
+
+impl Measure {
+    fn weight(&self, p: &PseudoJet) -> f64 {
+        match self {
+            Self::PtR => p.pt().into(),
+            Self::ETheta => p.e().into(),
+        }
+    }
+
+    fn angle(&self, p1: &PseudoJet, p2: &PseudoJet) -> f64 {
+        match self {
+            Self::PtR => p1.delta_r(p2).into(),
+            Self::ETheta => {
+                let [x1, y1, z1] = [p1.px(), p1.py(), p1.pz()].map(f64::from);
+                let [x2, y2, z2] = [p2.px(), p2.py(), p2.pz()].map(f64::from);
+                let cross =
+                    [y1 * z2 - z1 * y2, z1 * x2 - x1 * z2, x1 * y2 - y1 * x2];
+                let sin = cross.iter().map(|c| c * c).sum::<f64>().sqrt();
+                // more accurate than acos for small angles
+                sin.atan2(x1 * x2 + y1 * y2 + z1 * z2)
+            }
+        }
+    }
+}
+
+/// Calculator for energy correlation functions
+///
+/// # Examples
+///
+/// ```rust
+/// use jetty::{ecf::{EnergyCorrelator, Measure}, pseudojet_f};
+/// use noisy_float::prelude::*;
+///
+/// let constituents = [
+///     pseudojet_f(0.2626773221934335, -0.08809521946454194, -0.1141608706693822, -0.2195584284654444),
+///     pseudojet_f(2.21902459329915, -0.7529973704809976, -0.9658189214109036, -1.850475321845671)
+/// ];
+///
+/// let ecf = EnergyCorrelator::new(n64(2.), Measure::PtR).correlations(&constituents);
+/// assert!(ecf.e2() > 0.);
+/// assert_eq!(ecf.e3(), 0.);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EnergyCorrelator {
+    /// Angular exponent β
+    pub beta: N64,
+    /// Choice of momentum fractions and angles
+    pub measure: Measure,
+}
+
+impl EnergyCorrelator {
+    /// Energy correlator with angular exponent `beta` and the given
+    /// measure
+    pub fn new(beta: N64, measure: Measure) -> Self {
+        assert!(beta > 0.);
+        Self { beta, measure }
+    }
+
+    /// Energy correlation functions of the jet with the given
+    /// constituents
+    ///
+    /// All correlation functions are computed together in O(n^3)
+    /// time for n constituents.
+    pub fn correlations(
+        &self,
+        constituents: &[PseudoJet],
+    ) -> EnergyCorrelations {
+        let n = constituents.len();
+        let total: f64 =
+            constituents.iter().map(|p| self.measure.weight(p)).sum();
+        if total == 0. {
+            return EnergyCorrelations::default();
+        }
+        let z = Vec::from_iter(
+            constituents.iter().map(|p| self.measure.weight(p) / total),
+        );
+        // symmetric matrix of θ_ij^β
+        let beta = f64::from(self.beta);
+        let mut angles = vec![0.; n * n];
+        for (i, p1) in constituents.iter().enumerate() {
+            for (j, p2) in constituents.iter().enumerate().skip(i + 1) {
+                let angle = self.measure.angle(p1, p2).powf(beta);
+                angles[i * n + j] = angle;
+                angles[j * n + i] = angle;
+            }
+        }
+
+        let mut e2 = 0.;
+        let mut e3 = [0.; 3];
+        for i in 0..n {
+            let row_i = &angles[i * n..(i + 1) * n];
+            for j in (i + 1)..n {
+                let zij = z[i] * z[j];
+                let a = row_i[j];
+                e2 += zij * a;
+                let row_j = &angles[j * n..(j + 1) * n];
+                let mut e3_ij = [0.; 3];
+                for ((&zk, &b), &c) in
+                    z[j + 1..].iter().zip(&row_i[j + 1..]).zip(&row_j[j + 1..])
+                {
+                    let min = a.min(b).min(c);
+                    let mid = a.min(b).max(a.max(b).min(c));
+                    e3_ij[0] += zk * min;
+                    e3_ij[1] += zk * min * mid;
+                    e3_ij[2] += zk * b * c;
+                }
+                e3[0] += zij * e3_ij[0];
+                e3[1] += zij * e3_ij[1];
+                e3[2] += zij * a * e3_ij[2];
+            }
+        }
+        EnergyCorrelations {
+            e2: n64(e2),
+            e3: e3.map(n64),
+        }
+    }
+}
+
+/// Normalised energy correlation functions of a jet
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EnergyCorrelations {
+    e2: N64,
+    // _v e_3 for v = 1, 2, 3
+    e3: [N64; 3],
+}
+
+impl EnergyCorrelations {
+    /// Two-point correlation function e_2^(β)
+    pub fn e2(&self) -> N64 {
+        self.e2
+    }
+
+    /// Three-point correlation function e_3^(β)
+    pub fn e3(&self) -> N64 {
+        self.e3[2]
+    }
+
+    /// Generalised correlation function _v e_n^(β)
+    ///
+    /// Only n = 2 with v = 1 and n = 3 with v = 1, 2, 3 are
+    /// supported.
+    pub fn generalised(&self, v: usize, n: usize) -> N64 {
+        match (v, n) {
+            (1, 2) => self.e2,
+            (1..=3, 3) => self.e3[v - 1],
+            _ => panic!(
+                "Unsupported generalised energy correlation function {v}e{n}"
+            ),
+        }
+    }
+
+    /// The ratio C_2 = e_3/e_2^2
+    ///
+    /// As for all ratios, the result is zero if the denominator
+    /// vanishes.
+    pub fn c2(&self) -> N64 {
+        ratio(self.e3(), self.e2 * self.e2)
+    }
+
+    /// The ratio D_2 = e_3/e_2^3
+    pub fn d2(&self) -> N64 {
+        ratio(self.e3(), self.e2 * self.e2 * self.e2)
+    }
+
+    /// The ratio N_2 = _2e_3/(_1e_2)^2
+    pub fn n2(&self) -> N64 {
+        ratio(self.e3[1], self.e2 * self.e2)
+    }
+
+    /// The ratio M_2 = _1e_3/_1e_2
+    pub fn m2(&self) -> N64 {
+        ratio(self.e3[0], self.e2)
+    }
+}
+
+fn ratio(num: N64, denom: N64) -> N64 {
+    if denom > 0. {
+        num / denom
+    } else {
+        n64(0.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_data::*;
+
+    use super::*;
+
+    fn assert_close(a: N64, b: f64) {
+        assert!(f64::from((a - b).abs()) <= 1e-10 * b.abs(), "{a} != {b}");
+    }
+
+    // Straightforward evaluation of _v e_3 with explicit sorting
+    fn e3_naive(
+        ecf: &EnergyCorrelator,
+        partons: &[PseudoJet],
+        v: usize,
+    ) -> f64 {
+        let m = ecf.measure;
+        let total: f64 = partons.iter().map(|p| m.weight(p)).sum();
+        let mut res = 0.;
+        for (i, p1) in partons.iter().enumerate() {
+            for (j, p2) in partons.iter().enumerate().skip(i + 1) {
+                for p3 in &partons[j + 1..] {
+                    let mut angles =
+                        [m.angle(p1, p2), m.angle(p1, p3), m.angle(p2, p3)];
+                    angles.sort_by(f64::total_cmp);
+                    let angles: f64 = angles[..v].iter().product();
+                    let z: f64 =
+                        [p1, p2, p3].iter().map(|p| m.weight(p)).product();
+                    res += z / total.powi(3) * angles.powf(ecf.beta.into());
+                }
+            }
+        }
+        res
+    }
+
+    #[test]
+    fn tst_ecf_3_to_2() {
+        let partons = partons_3_to_2();
+        let expected = [
+            (
+                1.,
+                Measure::PtR,
+                [
+                    0.33851968848335534,
+                    0.008708177587935758,
+                    0.018497883440283056,
+                    0.045870930344509425,
+                    0.40028533865187055,
+                    1.1824580733996277,
+                    0.1614188219342016,
+                    0.0257242869002697,
+                ],
+            ),
+            (
+                1.,
+                Measure::ETheta,
+                [
+                    0.019730053556069597,
+                    0.00032353209287542227,
+                    8.296197116760354e-05,
+                    2.2686719058052558e-05,
+                    0.058279412449958407,
+                    2.953839546574864,
+                    0.2131191788008751,
+                    0.016397932826487105,
+                ],
+            ),
+            (
+                2.,
+                Measure::PtR,
+                [
+                    0.6503731993992589,
+                    0.0032215966763286205,
+                    0.014536528072714256,
+                    0.08939057096632629,
+                    0.21133257180331197,
+                    0.32494046802438525,
+                    0.03436650901195154,
+                    0.004953458536274814,
+                ],
+            ),
+            (
+                2.,
+                Measure::ETheta,
+                [
+                    0.0038427311827614165,
+                    9.149878462531558e-06,
+                    6.016427888521852e-07,
+                    4.499082708773849e-08,
+                    0.003046800147434703,
+                    0.7928736105982955,
+                    0.04074353499221269,
+                    0.002381087311956176,
+                ],
+            ),
+        ];
+        for (beta, measure, expected) in expected {
+            let ecf = EnergyCorrelator::new(n64(beta), measure)
+                .correlations(&partons);
+            let [e2, e3_1, e3_2, e3, c2, d2, n2, m2] = expected;
+            assert_close(ecf.e2(), e2);
+            assert_close(ecf.generalised(1, 2), e2);
+            assert_close(ecf.generalised(1, 3), e3_1);
+            assert_close(ecf.generalised(2, 3), e3_2);
+            assert_close(ecf.generalised(3, 3), e3);
+            assert_close(ecf.e3(), e3);
+            assert_close(ecf.c2(), c2);
+            assert_close(ecf.d2(), d2);
+            assert_close(ecf.n2(), n2);
+            assert_close(ecf.m2(), m2);
+        }
+    }
+
+    #[test]
+    fn tst_ecf_naive() {
+        let partons = partons_9_to_7();
+        for beta in [0.5, 1., 2.] {
+            for measure in [Measure::PtR, Measure::ETheta] {
+                let correlator = EnergyCorrelator::new(n64(beta), measure);
+                let ecf = correlator.correlations(&partons);
+                for v in 1..=3 {
+                    let expected = e3_naive(&correlator, &partons, v);
+                    assert_close(ecf.generalised(v, 3), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tst_ecf_few_constituents() {
+        let correlator = EnergyCorrelator::new(n64(1.), Measure::PtR);
+        assert_eq!(correlator.correlations(&[]), EnergyCorrelations::default());
+
+        let partons = partons_2_to_1();
+        let ecf = correlator.correlations(&partons);
+        // z (1 - z) ΔR with the values from the Lund plane tests
+        let z = 0.10534215118030102;
+        assert_close(ecf.e2(), z * (1. - z) * 0.008095547380574799);
+        assert_eq!(ecf.e3(), 0.);
+        assert_eq!(ecf.d2(), 0.);
+        assert_eq!(correlator.correlations(&partons[..1]).e2(), 0.);
+    }
+}
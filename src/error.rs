@@ -0,0 +1,57 @@
+use std::fmt::{self, Display};
+
+/// Errors arising from invalid input
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// The four-momentum component with the given index is NaN
+    NaN(usize),
+    /// The four-momentum component with the given index is infinite
+    Infinite(usize),
+    /// The energy is smaller than the absolute value of the
+    /// three-momentum
+    Spacelike {
+        /// Energy
+        e: f64,
+        /// Absolute value of the three-momentum
+        modp: f64,
+    },
+    /// Wrong number of four-momentum components
+    WrongLength(usize),
+    /// The input particle with the given index is invalid
+    InvalidParticle {
+        /// Index of the particle in the input
+        index: usize,
+        /// Why the particle is invalid
+        source: Box<Error>,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NaN(i) => write!(f, "four-momentum component {i} is NaN"),
+            Error::Infinite(i) => {
+                write!(f, "four-momentum component {i} is infinite")
+            }
+            Error::Spacelike { e, modp } => write!(
+                f,
+                "energy {e} is smaller than absolute three-momentum {modp}"
+            ),
+            Error::WrongLength(len) => {
+                write!(f, "expected four four-momentum components, got {len}")
+            }
+            Error::InvalidParticle { index, source } => {
+                write!(f, "input particle {index} is invalid: {source}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::InvalidParticle { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! Grooming removes soft and wide-angle radiation from jets, which
+//! reduces the sensitivity to pileup, underlying event, and
+//! non-perturbative effects. All groomers take the constituents of a
+//! jet as input.
+/// Pruning
+pub mod prune;
+/// Soft drop and the modified mass-drop tagger
+pub mod soft_drop;
+/// Trimming and filtering
+pub mod trim;
+
+use std::cmp::Reverse;
+
+use noisy_float::prelude::*;
+
+use crate::{
+    cambridge_aachen,
+    cluster::ClusterSequence,
+    distance::{Distance, Geometry},
+    kt, Cluster, PseudoJet,
+};
+
+pub use self::prune::Pruner;
+pub use self::soft_drop::{
+    IteratedSoftDrop, RecursiveSoftDrop, RecursiveSoftDropJet, SoftDrop,
+    SoftDropEmission, SoftDropJet,
+};
+pub use self::trim::{Filter, Trimmer};
+
+/// A groomed jet consisting of subjets
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GroomedJet {
+    /// The groomed jet, i.e. the sum of the kept subjets
+    pub jet: PseudoJet,
+    /// The kept subjets, ordered by decreasing transverse momentum
+    pub subjets: Vec<PseudoJet>,
+}
+
+impl GroomedJet {
+    fn from_subjets(subjets: Vec<PseudoJet>) -> Self {
+        Self {
+            jet: subjets.iter().sum(),
+            subjets,
+        }
+    }
+}
+
+/// Algorithm used for finding subjets
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SubjetAlgorithm {
+    /// The kt algorithm
+    Kt,
+    /// The Cambridge/Aachen algorithm
+    CambridgeAachen,
+}
+
+impl SubjetAlgorithm {
+    // Subjets with radius `r`, ordered by decreasing transverse
+    // momentum
+    fn subjets(&self, constituents: &[PseudoJet], r: N64) -> Vec<PseudoJet> {
+        let constituents = constituents.to_vec();
+        let mut subjets = match self {
+            Self::Kt => constituents.cluster(kt(r)),
+            Self::CambridgeAachen => constituents.cluster(cambridge_aachen(r)),
+        };
+        subjets.sort_by_key(|s| Reverse(s.pt2()));
+        subjets
+    }
+}
+
+// Distance measure without beam distance
+//
+// Clustering with this distance combines all pseudojets into a
+// single jet, so no constituents are lost to the beam. Since the
+// distance has no radius, clustering never uses tiles.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct WithoutBeam<D>(pub(crate) D);
+
+impl<D: Distance> Distance for WithoutBeam<D> {
+    fn distance(&self, p1: &PseudoJet, p2: &PseudoJet) -> N64 {
+        self.0.distance(p1, p2)
+    }
+
+    fn beam_distance(&self, _p1: &PseudoJet) -> N64 {
+        N64::infinity()
+    }
+
+    fn geometry(&self) -> Geometry {
+        self.0.geometry()
+    }
+}
+
+// Constituents reclustered into a single jet with the
+// Cambridge/Aachen algorithm
+#[derive(Clone, Debug)]
+pub(crate) struct Reclustered {
+    seq: ClusterSequence,
+    jet: usize,
+}
+
+impl Reclustered {
+    // `None` if there are no constituents
+    pub(crate) fn new(constituents: &[PseudoJet]) -> Option<Self> {
+        let seq = ClusterSequence::new(
+            constituents.to_vec(),
+            WithoutBeam(cambridge_aachen(n64(1.))),
+        );
+        debug_assert!(seq.jets().len() <= 1);
+        let &jet = seq.jets().first()?;
+        Some(Self { seq, jet })
+    }
+
+    // History index of the jet
+    pub(crate) fn jet(&self) -> usize {
+        self.jet
+    }
+
+    pub(crate) fn pseudojet(&self, idx: usize) -> PseudoJet {
+        self.seq.history()[idx].pseudojet
+    }
+
+    // The two pseudojets combined into the one at `idx`, with the
+    // one with larger transverse momentum first
+    pub(crate) fn parents(&self, idx: usize) -> Option<[usize; 2]> {
+        let [a, b] = self.seq.history()[idx].parents?;
+        if self.pseudojet(a).pt2() >= self.pseudojet(b).pt2() {
+            Some([a, b])
+        } else {
+            Some([b, a])
+        }
+    }
+
+    // Undo the clustering step that produced the pseudojet at `idx`
+    pub(crate) fn split(&self, idx: usize) -> Option<Split> {
+        let [harder, softer] = self.parents(idx)?;
+        let [harder_jet, softer_jet] =
+            [harder, softer].map(|i| self.pseudojet(i));
+        Some(Split {
+            harder,
+            softer,
+            z: softer_jet.pt() / (harder_jet.pt() + softer_jet.pt()),
+            delta_r: harder_jet.delta_r(&softer_jet),
+        })
+    }
+}
+
+// A single declustering step
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Split {
+    // History index of the branch with larger transverse momentum
+    pub(crate) harder: usize,
+    // History index of the branch with smaller transverse momentum
+    pub(crate) softer: usize,
+    // Transverse momentum fraction of the softer branch
+    pub(crate) z: N64,
+    // Distance ΔR between the branches
+    pub(crate) delta_r: N64,
+}
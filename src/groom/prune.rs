@@ -0,0 +1,220 @@
+use noisy_float::prelude::*;
+
+use crate::{
+    cambridge_aachen,
+    distance::Distance,
+    groom::{SubjetAlgorithm, WithoutBeam},
+    kt,
+    recombine::Recombiner,
+    ClusterHistory, ClusterStep, PseudoJet,
+};
+
+/// Jet pruning, see [arXiv:0903.5081](https://arxiv.org/abs/0903.5081)
+///
+/// The jet constituents are reclustered. Whenever two pseudojets
+/// with transverse momenta pt_i, pt_j are combined into a pseudojet
+/// with transverse momentum pt_ij, the softer one is discarded if
+///
+/// min(pt_i, pt_j) / pt_ij < z_cut and ΔR_ij > r_cut_factor · 2 m / pt
+///
+/// where m and pt are the mass and transverse momentum of the
+/// original jet.
+///
+/// # Examples
+///
+/// ```rust
+/// use jetty::{groom::Pruner, pseudojet_f};
+/// use noisy_float::prelude::*;
+///
+/// let constituents = [
+///     pseudojet_f(0.2626773221934335, -0.08809521946454194, -0.1141608706693822, -0.2195584284654444),
+///     pseudojet_f(2.21902459329915, -0.7529973704809976, -0.9658189214109036, -1.850475321845671)
+/// ];
+///
+/// let pruner = Pruner::new(n64(0.1), n64(0.5));
+/// let pruned = pruner.groom(&constituents);
+/// assert!(pruned.pt() > 0.);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Pruner {
+    /// Cut on the momentum fraction of the softer pseudojet
+    pub z_cut: N64,
+    /// Angular cut relative to 2 m / pt of the original jet
+    pub r_cut_factor: N64,
+    /// Algorithm used for reclustering
+    pub algorithm: SubjetAlgorithm,
+}
+
+impl Pruner {
+    /// Pruning with cuts `z_cut` and `r_cut_factor`, using the
+    /// Cambridge/Aachen algorithm for reclustering
+    pub fn new(z_cut: N64, r_cut_factor: N64) -> Self {
+        Self {
+            z_cut,
+            r_cut_factor,
+            algorithm: SubjetAlgorithm::CambridgeAachen,
+        }
+    }
+
+    /// Prune the jet with the given constituents
+    ///
+    /// Returns a pseudojet with vanishing four-momentum if there are
+    /// no constituents.
+    pub fn groom(&self, constituents: &[PseudoJet]) -> PseudoJet {
+        let jet: PseudoJet = constituents.iter().sum();
+        if jet.pt2() == 0. {
+            return jet;
+        }
+        let recombiner = PruningRecombiner {
+            z_cut: self.z_cut,
+            r_cut: self.r_cut_factor * jet.m() * 2. / jet.pt(),
+        };
+        let r = n64(1.);
+        match self.algorithm {
+            SubjetAlgorithm::Kt => {
+                prune(constituents, WithoutBeam(kt(r)), recombiner)
+            }
+            SubjetAlgorithm::CambridgeAachen => prune(
+                constituents,
+                WithoutBeam(cambridge_aachen(r)),
+                recombiner,
+            ),
+        }
+    }
+}
+
+// Cluster into a single jet with the pruning condition
+fn prune<D: Distance>(
+    constituents: &[PseudoJet],
+    distance: D,
+    recombiner: PruningRecombiner,
+) -> PseudoJet {
+    let mut history = ClusterHistory::with_recombiner(
+        constituents.to_vec(),
+        distance,
+        recombiner,
+    );
+    history
+        .find_map(|step| match step {
+            ClusterStep::Jet(jet) => Some(jet),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+// E scheme recombination discarding the softer pseudojet if the
+// pruning condition fails
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct PruningRecombiner {
+    z_cut: N64,
+    r_cut: N64,
+}
+
+impl Recombiner for PruningRecombiner {
+    fn recombine(&self, p1: &PseudoJet, p2: &PseudoJet) -> PseudoJet {
+        let combined = *p1 + *p2;
+        let (harder, softer) = if p1.pt2() >= p2.pt2() {
+            (p1, p2)
+        } else {
+            (p2, p1)
+        };
+        let z = softer.pt() / combined.pt();
+        if z < self.z_cut && p1.delta_r(p2) > self.r_cut {
+            // like any recombined pseudojet, the result has no user
+            // index
+            let mut res = *harder;
+            res.reset_user_index();
+            res
+        } else {
+            combined
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_data::*;
+
+    use super::*;
+
+    fn massless(pt: f64, y: f64, phi: f64) -> PseudoJet {
+        PseudoJet::from_pt_y_phi_m(n64(pt), n64(y), n64(phi), n64(0.))
+    }
+
+    #[test]
+    fn tst_pruner() {
+        let core = [massless(100., 0., 1.), massless(50., 0.1, 1.)];
+        let soft = massless(1., 0.7, 1.2);
+        let constituents = [soft, core[0], core[1]];
+
+        for algorithm in [SubjetAlgorithm::CambridgeAachen, SubjetAlgorithm::Kt]
+        {
+            let pruner = Pruner {
+                algorithm,
+                ..Pruner::new(n64(0.1), n64(0.5))
+            };
+            assert_eq!(pruner.groom(&constituents), core[0] + core[1]);
+        }
+
+        let unpruned = Pruner::new(n64(0.), n64(0.5)).groom(&constituents);
+        let total: PseudoJet = constituents.iter().sum();
+        for i in 0..4 {
+            assert!((unpruned[i] - total[i]).abs() < 1e-10);
+        }
+
+        // the pruned jet is not an input particle
+        let hard = core[0].with_user_index(0);
+        let pruned = Pruner::new(n64(0.1), n64(0.5)).groom(&[hard, soft]);
+        assert_eq!(pruned.user_index(), None);
+        for i in 0..4 {
+            assert_eq!(pruned[i], hard[i]);
+        }
+
+        // large angular cut
+        let unpruned = Pruner::new(n64(0.1), n64(10.)).groom(&constituents);
+        assert!((unpruned.pt() - total.pt()).abs() < 1e-10);
+
+        assert_eq!(
+            Pruner::new(n64(0.1), n64(0.5)).groom(&[]),
+            PseudoJet::new()
+        );
+    }
+
+    #[test]
+    fn tst_pruner_wide() {
+        let (prongs, forward) = wide_jet();
+        let soft = [massless(0.5, 1., 2.5), forward];
+        let constituents = [&prongs[0][..], &prongs[1], &soft].concat();
+
+        let expected: PseudoJet = prongs.concat().iter().sum();
+        for algorithm in [SubjetAlgorithm::CambridgeAachen, SubjetAlgorithm::Kt]
+        {
+            let pruner = Pruner {
+                algorithm,
+                ..Pruner::new(n64(0.1), n64(0.5))
+            };
+            let pruned = pruner.groom(&constituents);
+            for i in 0..4 {
+                assert!((pruned[i] - expected[i]).abs() < 1e-10);
+            }
+        }
+    }
+
+    #[test]
+    fn tst_pruner_event() {
+        // enough particles for the geometric clustering
+        let mut constituents = partons_9_to_7();
+        constituents.extend(partons_8_to_7());
+        constituents.extend(partons_4_to_4());
+        constituents.extend(partons_3_to_2());
+        constituents.extend(partons_2_to_1());
+        assert!(constituents.len() > 25);
+        let total: PseudoJet = constituents.iter().sum();
+        let pruned = Pruner::new(n64(0.1), n64(0.5)).groom(&constituents);
+        assert!(pruned.pt() > 0.);
+        assert!(pruned.e() <= total.e() * (1. + 1e-10));
+
+        let pruned = Pruner::new(n64(0.5), n64(0.)).groom(&constituents);
+        assert!(pruned.e() < total.e());
+    }
+}
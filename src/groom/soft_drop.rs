@@ -0,0 +1,408 @@
+use noisy_float::prelude::*;
+
+use crate::{groom::Reclustered, PseudoJet};
+
+/// Soft drop grooming, see [arXiv:1402.2657](https://arxiv.org/abs/1402.2657)
+///
+/// The jet constituents are reclustered with the Cambridge/Aachen
+/// algorithm. The clustering is then undone step by step, dropping
+/// the softer of the two branches until
+///
+/// min(pt_1, pt_2) / (pt_1 + pt_2) > z_cut (ΔR_12 / R0)^β
+///
+/// For β = 0 this is the modified mass-drop tagger (mMDT).
+///
+/// # Examples
+///
+/// ```rust
+/// use jetty::{groom::SoftDrop, pseudojet_f};
+/// use noisy_float::prelude::*;
+///
+/// let constituents = [
+///     pseudojet_f(0.2626773221934335, -0.08809521946454194, -0.1141608706693822, -0.2195584284654444),
+///     pseudojet_f(2.21902459329915, -0.7529973704809976, -0.9658189214109036, -1.850475321845671)
+/// ];
+///
+/// let soft_drop = SoftDrop::new(n64(0.), n64(0.1), n64(0.8));
+/// let groomed = soft_drop.groom(&constituents).unwrap();
+/// assert_eq!(groomed.n_dropped, 0);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SoftDrop {
+    /// Angular exponent β
+    pub beta: N64,
+    /// Cut z_cut on the momentum fraction of the softer branch
+    pub z_cut: N64,
+    /// Characteristic radius R0, usually the jet radius
+    pub r0: N64,
+}
+
+/// Result of soft drop grooming
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SoftDropJet {
+    /// The groomed jet
+    pub jet: PseudoJet,
+    /// Momentum fraction of the softer branch in the declustering
+    /// step that passed the soft drop condition
+    ///
+    /// This is zero if no step passed.
+    pub z_g: N64,
+    /// Distance ΔR between the two branches in the declustering
+    /// step that passed the soft drop condition
+    ///
+    /// This is zero if no step passed.
+    pub r_g: N64,
+    /// Number of dropped branches
+    pub n_dropped: usize,
+}
+
+impl SoftDrop {
+    /// Soft drop with angular exponent `beta`, cut `z_cut` on the
+    /// momentum fraction, and characteristic radius `r0`
+    pub fn new(beta: N64, z_cut: N64, r0: N64) -> Self {
+        Self { beta, z_cut, r0 }
+    }
+
+    /// Modified mass-drop tagger with cut `z_cut` on the momentum
+    /// fraction, i.e. soft drop with β = 0
+    pub fn mmdt(z_cut: N64) -> Self {
+        Self::new(n64(0.), z_cut, n64(1.))
+    }
+
+    /// Groom the jet with the given constituents
+    ///
+    /// Returns `None` if there are no constituents.
+    pub fn groom(&self, constituents: &[PseudoJet]) -> Option<SoftDropJet> {
+        let jet = Reclustered::new(constituents)?;
+        let mut idx = jet.jet();
+        let mut n_dropped = 0;
+        while let Some(split) = jet.split(idx) {
+            if self.passes(split.z, split.delta_r) {
+                return Some(SoftDropJet {
+                    jet: jet.pseudojet(idx),
+                    z_g: split.z,
+                    r_g: split.delta_r,
+                    n_dropped,
+                });
+            }
+            n_dropped += 1;
+            idx = split.harder;
+        }
+        Some(SoftDropJet {
+            jet: jet.pseudojet(idx),
+            z_g: n64(0.),
+            r_g: n64(0.),
+            n_dropped,
+        })
+    }
+
+    // Whether a declustering with momentum fraction `z` and distance
+    // `delta_r` passes the soft drop condition
+    pub(crate) fn passes(&self, z: N64, delta_r: N64) -> bool {
+        z > self.z_cut * (delta_r / self.r0).powf(self.beta)
+    }
+}
+
+/// Recursive soft drop, see [arXiv:1804.03657](https://arxiv.org/abs/1804.03657)
+///
+/// Instead of stopping at the first declustering passing the soft
+/// drop condition, both branches are groomed further until the
+/// condition has been passed `n` times. At each step, the branch
+/// with the largest distance between its two subbranches is
+/// declustered.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RecursiveSoftDrop {
+    /// Soft drop condition applied in each step
+    pub soft_drop: SoftDrop,
+    /// Number of declusterings passing the soft drop condition
+    ///
+    /// Use `usize::MAX` to groom until all branches consist of
+    /// single constituents.
+    pub n: usize,
+}
+
+/// Result of recursive soft drop grooming
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RecursiveSoftDropJet {
+    /// The groomed jet, i.e. the sum of all prongs
+    pub jet: PseudoJet,
+    /// The remaining branches
+    pub prongs: Vec<PseudoJet>,
+    /// Number of dropped branches
+    pub n_dropped: usize,
+}
+
+impl RecursiveSoftDrop {
+    /// Recursive soft drop with angular exponent `beta`, cut `z_cut`
+    /// on the momentum fraction, characteristic radius `r0`, and `n`
+    /// declusterings passing the soft drop condition
+    pub fn new(beta: N64, z_cut: N64, r0: N64, n: usize) -> Self {
+        Self {
+            soft_drop: SoftDrop::new(beta, z_cut, r0),
+            n,
+        }
+    }
+
+    /// Groom the jet with the given constituents
+    ///
+    /// Returns `None` if there are no constituents.
+    pub fn groom(
+        &self,
+        constituents: &[PseudoJet],
+    ) -> Option<RecursiveSoftDropJet> {
+        let jet = Reclustered::new(constituents)?;
+        let mut prongs = vec![jet.jet()];
+        let mut n_passed = 0;
+        let mut n_dropped = 0;
+        while n_passed < self.n {
+            // decluster the prong with the widest splitting
+            let widest = prongs
+                .iter()
+                .enumerate()
+                .filter_map(|(pos, &idx)| Some((pos, jet.split(idx)?)))
+                .max_by_key(|(_, split)| split.delta_r);
+            let Some((pos, split)) = widest else {
+                break;
+            };
+            if self.soft_drop.passes(split.z, split.delta_r) {
+                prongs[pos] = split.harder;
+                prongs.push(split.softer);
+                n_passed += 1;
+            } else {
+                prongs[pos] = split.harder;
+                n_dropped += 1;
+            }
+        }
+        let prongs =
+            Vec::from_iter(prongs.into_iter().map(|i| jet.pseudojet(i)));
+        Some(RecursiveSoftDropJet {
+            jet: prongs.iter().sum(),
+            prongs,
+            n_dropped,
+        })
+    }
+}
+
+/// Iterated soft drop, see [arXiv:1704.06266](https://arxiv.org/abs/1704.06266)
+///
+/// The jet is declustered along the branch with larger transverse
+/// momentum. Softer branches are dropped if they fail the soft drop
+/// condition and recorded as emissions otherwise. Declustering stops
+/// when the distance between the branches falls below `theta_cut`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IteratedSoftDrop {
+    /// Soft drop condition applied in each step
+    pub soft_drop: SoftDrop,
+    /// Minimum distance ΔR between the branches
+    pub theta_cut: N64,
+}
+
+/// Emission found by iterated soft drop
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SoftDropEmission {
+    /// Momentum fraction of the softer branch
+    pub z_g: N64,
+    /// Distance ΔR between the branches
+    pub theta_g: N64,
+}
+
+impl IteratedSoftDrop {
+    /// Iterated soft drop with angular exponent `beta`, cut `z_cut`
+    /// on the momentum fraction, characteristic radius `r0`, and
+    /// angular cut `theta_cut`
+    pub fn new(beta: N64, z_cut: N64, r0: N64, theta_cut: N64) -> Self {
+        Self {
+            soft_drop: SoftDrop::new(beta, z_cut, r0),
+            theta_cut,
+        }
+    }
+
+    /// Emissions along the primary branch of the jet with the given
+    /// constituents, starting with the widest one
+    ///
+    /// The soft drop multiplicity is the number of emissions.
+    pub fn emissions(
+        &self,
+        constituents: &[PseudoJet],
+    ) -> Vec<SoftDropEmission> {
+        let Some(jet) = Reclustered::new(constituents) else {
+            return Vec::new();
+        };
+        let mut emissions = Vec::new();
+        let mut idx = jet.jet();
+        while let Some(split) = jet.split(idx) {
+            if split.delta_r < self.theta_cut {
+                break;
+            }
+            if self.soft_drop.passes(split.z, split.delta_r) {
+                emissions.push(SoftDropEmission {
+                    z_g: split.z,
+                    theta_g: split.delta_r,
+                });
+            }
+            idx = split.harder;
+        }
+        emissions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_data::*;
+
+    use super::*;
+
+    fn massless(pt: f64, y: f64, phi: f64) -> PseudoJet {
+        PseudoJet::from_pt_y_phi_m(n64(pt), n64(y), n64(phi), n64(0.))
+    }
+
+    #[test]
+    fn tst_soft_drop() {
+        let core = [massless(100., 0., 1.), massless(50., 0.1, 1.)];
+        let soft = massless(1., 0.8, 1.);
+        let constituents = [core[0], core[1], soft];
+
+        for soft_drop in [
+            SoftDrop::mmdt(n64(0.1)),
+            SoftDrop::new(n64(2.), n64(0.1), n64(0.8)),
+        ] {
+            let groomed = soft_drop.groom(&constituents).unwrap();
+            assert_eq!(groomed.n_dropped, 1);
+            assert_eq!(groomed.jet, core[0] + core[1]);
+            assert!((groomed.z_g - 1. / 3.).abs() < 1e-10);
+            assert!((groomed.r_g - 0.1).abs() < 1e-10);
+        }
+
+        // with a large β, only the wide-angle soft emission is
+        // checked against a strong cut
+        let groomed = SoftDrop::new(n64(1.), n64(0.005), n64(0.8))
+            .groom(&constituents)
+            .unwrap();
+        assert_eq!(groomed.n_dropped, 0);
+        assert_eq!(groomed.jet, core[0] + core[1] + soft);
+
+        // nothing passes
+        let groomed = SoftDrop::mmdt(n64(0.5)).groom(&constituents).unwrap();
+        assert_eq!(groomed.n_dropped, 2);
+        assert_eq!(groomed.jet, core[0]);
+        assert_eq!(groomed.z_g, 0.);
+        assert_eq!(groomed.r_g, 0.);
+
+        assert_eq!(SoftDrop::mmdt(n64(0.1)).groom(&[]), None);
+    }
+
+    #[test]
+    fn tst_recursive_soft_drop() {
+        // two prongs with a soft wide-angle emission each
+        let prong1 = [massless(100., 0., 1.), massless(30., 0.05, 1.05)];
+        let prong2 = [massless(60., 0.6, 1.), massless(20., 0.63, 0.98)];
+        let soft = [massless(0.5, -0.4, 1.), massless(0.3, 0.6, 1.5)];
+        let constituents = [prong1, prong2, soft].concat();
+
+        let rsd = RecursiveSoftDrop::new(n64(0.), n64(0.1), n64(1.), 1);
+        let groomed = rsd.groom(&constituents).unwrap();
+        let sd = SoftDrop::mmdt(n64(0.1)).groom(&constituents).unwrap();
+        assert_eq!(groomed.jet, sd.jet);
+        assert_eq!(groomed.n_dropped, sd.n_dropped);
+        assert_eq!(groomed.prongs.len(), 2);
+
+        let rsd = RecursiveSoftDrop { n: 3, ..rsd };
+        let groomed = rsd.groom(&constituents).unwrap();
+        assert_eq!(groomed.prongs.len(), 4);
+        assert_eq!(groomed.n_dropped, 2);
+        let hard: PseudoJet = [prong1, prong2].concat().iter().sum();
+        for i in 0..4 {
+            assert!((groomed.jet[i] - hard[i]).abs() < 1e-10);
+        }
+
+        let rsd = RecursiveSoftDrop {
+            n: usize::MAX,
+            ..rsd
+        };
+        let groomed = rsd.groom(&constituents).unwrap();
+        assert_eq!(
+            groomed.prongs.len() + groomed.n_dropped,
+            constituents.len()
+        );
+
+        assert_eq!(rsd.groom(&[]), None);
+    }
+
+    #[test]
+    fn tst_iterated_soft_drop() {
+        let constituents = [
+            massless(100., 0., 1.),
+            massless(30., 0.05, 1.05),
+            massless(40., 0.3, 1.2),
+            massless(0.5, -0.6, 1.),
+        ];
+        let isd = IteratedSoftDrop::new(n64(0.), n64(0.1), n64(1.), n64(0.));
+        let emissions = isd.emissions(&constituents);
+        assert_eq!(emissions.len(), 2);
+        assert!(emissions[0].theta_g > emissions[1].theta_g);
+        assert!((emissions[1].theta_g - 0.05 * 2f64.sqrt()).abs() < 1e-10);
+        assert!((emissions[1].z_g - 30. / 130.).abs() < 1e-10);
+
+        let isd = IteratedSoftDrop {
+            theta_cut: n64(0.1),
+            ..isd
+        };
+        assert_eq!(isd.emissions(&constituents).len(), 1);
+        assert!(isd.emissions(&[]).is_empty());
+    }
+
+    #[test]
+    fn tst_soft_drop_wide() {
+        let (prongs, forward) = wide_jet();
+        let constituents = [&prongs[0][..], &prongs[1], &[forward]].concat();
+
+        let groomed = SoftDrop::mmdt(n64(0.1)).groom(&constituents).unwrap();
+        assert_eq!(groomed.n_dropped, 1);
+        let expected: PseudoJet = prongs.concat().iter().sum();
+        for i in 0..4 {
+            assert!((groomed.jet[i] - expected[i]).abs() < 1e-10);
+        }
+        assert!((groomed.z_g - 240. / 540.).abs() < 1e-10);
+        assert!((groomed.r_g - 2.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn tst_recursive_soft_drop_wide() {
+        let (prongs, forward) = wide_jet();
+        let constituents = [&prongs[0][..], &prongs[1], &[forward]].concat();
+        let expected: PseudoJet = prongs.concat().iter().sum();
+
+        let rsd = RecursiveSoftDrop::new(n64(0.), n64(0.1), n64(1.), 1);
+        let groomed = rsd.groom(&constituents).unwrap();
+        assert_eq!(groomed.prongs.len(), 2);
+        assert_eq!(groomed.n_dropped, 1);
+        for i in 0..4 {
+            assert!((groomed.jet[i] - expected[i]).abs() < 1e-10);
+        }
+
+        let isd = IteratedSoftDrop::new(n64(0.), n64(0.1), n64(1.), n64(0.5));
+        let emissions = isd.emissions(&constituents);
+        assert_eq!(emissions.len(), 1);
+        assert!((emissions[0].z_g - 240. / 540.).abs() < 1e-10);
+        assert!((emissions[0].theta_g - 2.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn tst_soft_drop_event() {
+        let constituents = partons_9_to_7();
+        let total_pt = constituents
+            .iter()
+            .fold(PseudoJet::new(), |s, p| s + *p)
+            .pt();
+        let groomed = SoftDrop::new(n64(1.), n64(0.1), n64(1.))
+            .groom(&constituents)
+            .unwrap();
+        assert!(groomed.n_dropped < constituents.len());
+        assert!(groomed.jet.pt() <= total_pt + 1e-10);
+        assert!(groomed.z_g > groomed.r_g * 0.1);
+
+        let ungroomed = SoftDrop::mmdt(n64(0.)).groom(&constituents).unwrap();
+        assert_eq!(ungroomed.n_dropped, 0);
+        assert!((ungroomed.jet.pt() - total_pt).abs() < 1e-10);
+    }
+}
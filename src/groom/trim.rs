@@ -0,0 +1,158 @@
+use noisy_float::prelude::*;
+
+use crate::{
+    groom::{GroomedJet, SubjetAlgorithm},
+    PseudoJet,
+};
+
+/// Jet trimming, see [arXiv:0912.1342](https://arxiv.org/abs/0912.1342)
+///
+/// The jet constituents are reclustered into subjets with radius
+/// `r_sub`. Only subjets with at least a fraction `f_cut` of the
+/// transverse momentum of the original jet are kept.
+///
+/// # Examples
+///
+/// ```rust
+/// use jetty::{groom::Trimmer, pseudojet_f};
+/// use noisy_float::prelude::*;
+///
+/// let constituents = [
+///     pseudojet_f(0.2626773221934335, -0.08809521946454194, -0.1141608706693822, -0.2195584284654444),
+///     pseudojet_f(2.21902459329915, -0.7529973704809976, -0.9658189214109036, -1.850475321845671)
+/// ];
+///
+/// let trimmer = Trimmer::new(n64(0.2), n64(0.03));
+/// let trimmed = trimmer.groom(&constituents);
+/// assert_eq!(trimmed.subjets.len(), 1);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Trimmer {
+    /// Subjet radius
+    pub r_sub: N64,
+    /// Minimum fraction of the jet transverse momentum in a subjet
+    pub f_cut: N64,
+    /// Algorithm used for finding subjets
+    pub algorithm: SubjetAlgorithm,
+}
+
+impl Trimmer {
+    /// Trimming with subjet radius `r_sub` and minimum transverse
+    /// momentum fraction `f_cut`, using the kt algorithm for finding
+    /// subjets
+    pub fn new(r_sub: N64, f_cut: N64) -> Self {
+        Self {
+            r_sub,
+            f_cut,
+            algorithm: SubjetAlgorithm::Kt,
+        }
+    }
+
+    /// Trim the jet with the given constituents
+    pub fn groom(&self, constituents: &[PseudoJet]) -> GroomedJet {
+        let pt_min = constituents.iter().sum::<PseudoJet>().pt() * self.f_cut;
+        let subjets = self.algorithm.subjets(constituents, self.r_sub);
+        GroomedJet::from_subjets(
+            subjets.into_iter().filter(|s| s.pt() >= pt_min).collect(),
+        )
+    }
+}
+
+/// Jet filtering, see [arXiv:0802.2470](https://arxiv.org/abs/0802.2470)
+///
+/// The jet constituents are reclustered into subjets with radius
+/// `r_filt`. Only the `n_filt` subjets with the largest transverse
+/// momentum are kept.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Filter {
+    /// Subjet radius
+    pub r_filt: N64,
+    /// Number of subjets to keep
+    pub n_filt: usize,
+    /// Algorithm used for finding subjets
+    pub algorithm: SubjetAlgorithm,
+}
+
+impl Filter {
+    /// Filtering with subjet radius `r_filt` keeping `n_filt`
+    /// subjets, using the Cambridge/Aachen algorithm for finding
+    /// subjets
+    pub fn new(r_filt: N64, n_filt: usize) -> Self {
+        Self {
+            r_filt,
+            n_filt,
+            algorithm: SubjetAlgorithm::CambridgeAachen,
+        }
+    }
+
+    /// Filter the jet with the given constituents
+    pub fn groom(&self, constituents: &[PseudoJet]) -> GroomedJet {
+        let mut subjets = self.algorithm.subjets(constituents, self.r_filt);
+        subjets.truncate(self.n_filt);
+        GroomedJet::from_subjets(subjets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_data::*;
+
+    use super::*;
+
+    fn massless(pt: f64, y: f64, phi: f64) -> PseudoJet {
+        PseudoJet::from_pt_y_phi_m(n64(pt), n64(y), n64(phi), n64(0.))
+    }
+
+    fn constituents() -> [PseudoJet; 5] {
+        [
+            massless(100., 0., 1.),
+            massless(5., 0.05, 1.05),
+            massless(40., 0.5, 1.),
+            massless(2., -0.4, 1.3),
+            massless(1., 0.6, 0.5),
+        ]
+    }
+
+    #[test]
+    fn tst_trimmer() {
+        let c = constituents();
+        let trimmed = Trimmer::new(n64(0.2), n64(0.03)).groom(&c);
+        assert_eq!(trimmed.subjets.len(), 2);
+        assert_eq!(trimmed.subjets[0], c[0] + c[1]);
+        assert_eq!(trimmed.subjets[1], c[2]);
+        assert_eq!(trimmed.jet, c[0] + c[1] + c[2]);
+
+        let untrimmed = Trimmer::new(n64(0.2), n64(0.)).groom(&c);
+        assert_eq!(untrimmed.subjets.len(), 4);
+        let total: PseudoJet = c.iter().sum();
+        for i in 0..4 {
+            assert!((untrimmed.jet[i] - total[i]).abs() < 1e-10);
+        }
+
+        let trimmed = Trimmer {
+            algorithm: SubjetAlgorithm::CambridgeAachen,
+            ..Trimmer::new(n64(0.2), n64(0.5))
+        }
+        .groom(&c);
+        assert_eq!(trimmed.subjets, [c[0] + c[1]]);
+
+        let empty = Trimmer::new(n64(0.2), n64(0.03)).groom(&[]);
+        assert_eq!(empty.jet, PseudoJet::new());
+        assert!(empty.subjets.is_empty());
+    }
+
+    #[test]
+    fn tst_filter() {
+        let c = constituents();
+        let filtered = Filter::new(n64(0.3), 3).groom(&c);
+        assert_eq!(filtered.subjets.len(), 3);
+        assert_eq!(filtered.subjets[0], c[0] + c[1]);
+        assert_eq!(filtered.subjets[1], c[2]);
+        assert_eq!(filtered.subjets[2], c[3]);
+
+        let partons = partons_9_to_7();
+        let filtered = Filter::new(n64(0.4), 2).groom(&partons);
+        assert_eq!(filtered.subjets.len(), 2);
+        assert!(filtered.subjets[0].pt() >= filtered.subjets[1].pt());
+    }
+}
@@ -5,7 +5,9 @@
 //! - [anti-kt](https://arxiv.org/abs/0802.1189)
 //! - [Cambridge](https://arxiv.org/abs/hep-ph/9707323)/[Aachen](https://arxiv.org/abs/hep-ph/9907280)
 //! - [kt](https://arxiv.org/abs/hep-ph/9305266)
-//! - Generalised kt.
+//! - Generalised kt
+//! - Durham, i.e. kt for lepton collisions
+//! - Generalised kt for lepton collisions.
 //!
 //! For state-of-the-art implementations of many more jet algorithms,
 //! have a look at the excellent [fastjet](http://fastjet.fr/)
@@ -43,12 +45,28 @@
 //!    }
 //! }
 //! ```
+/// Jet areas
+pub mod area;
+/// Background estimation and subtraction
+pub mod background;
 /// Jet clustering algorithms
 pub mod cluster;
 /// Distances and jet definitions
 pub mod distance;
+/// Energy correlation functions
+pub mod ecf;
+/// Error handling
+pub mod error;
+/// Jet grooming
+pub mod groom;
+/// Lund plane
+pub mod lund;
+/// N-subjettiness
+pub mod nsubjettiness;
 /// Pseudojets
 pub mod pseudojet;
+/// Recombination schemes
+pub mod recombine;
 
 #[cfg(test)]
 mod test_data;
@@ -56,15 +74,27 @@ mod test_data;
 #[allow(deprecated)]
 pub use cluster::{cluster, cluster_if};
 
-pub use cluster::{Cluster, ClusterHistory, ClusterStep};
-pub use distance::{anti_kt, cambridge_aachen, gen_kt, kt};
-pub use distance::{anti_kt_f, cambridge_aachen_f, gen_kt_f, kt_f};
+pub use cluster::{
+    Cluster, ClusterExt, ClusterHistory, ClusterHistoryWithDist,
+    ClusterSequence, ClusterStep, ClusterStepWithDist, TryClusterExt,
+};
+pub use distance::{anti_kt, cambridge_aachen, ee_gen_kt, ee_kt, gen_kt, kt};
+pub use distance::{
+    anti_kt_f, cambridge_aachen_f, ee_gen_kt_f, ee_kt_f, gen_kt_f, kt_f,
+};
+pub use error::Error;
 pub use pseudojet::{pseudojet, pseudojet_f, PseudoJet};
 
 #[cfg(test)]
 mod tests {
-    use super::{anti_kt_f, Cluster};
+    use super::{
+        anti_kt_f, distance::Distance, ee_gen_kt_f, ee_kt_f, kt_f, Cluster,
+        ClusterExt, ClusterHistory, ClusterSequence, ClusterStep, Error,
+        PseudoJet, TryClusterExt,
+    };
     use crate::test_data::*;
+    use noisy_float::prelude::*;
+    use std::cmp::min;
 
     fn log_init() {
         let _ = env_logger::builder().is_test(true).try_init();
@@ -80,6 +110,34 @@ mod tests {
         assert_eq!(jets.len(), 7);
     }
 
+    #[test]
+    fn tst_try_cluster() {
+        log_init();
+
+        let mut partons = partons_9_to_7();
+        assert_eq!(
+            partons.clone().try_cluster(anti_kt_f(0.4)),
+            Ok(partons.clone().cluster(anti_kt_f(0.4)))
+        );
+
+        partons[3] = crate::pseudojet_f(1., 0., 0., 2.);
+        let err = partons.try_cluster(anti_kt_f(0.4)).unwrap_err();
+        assert!(matches!(err, Error::InvalidParticle { index: 3, .. }));
+
+        // invalid components are detected before any conversion
+        let components = [[2., 1., 0., 0.], [1., 0., f64::NAN, 0.]];
+        let err = components.as_slice().try_cluster(anti_kt_f(0.4));
+        assert_eq!(
+            err,
+            Err(Error::InvalidParticle {
+                index: 1,
+                source: Box::new(Error::NaN(2))
+            })
+        );
+        let components = [(2., 1., 0., 0.), (1., 0., 0., 0.5)];
+        assert!(components.as_slice().try_cluster(anti_kt_f(0.4)).is_ok());
+    }
+
     #[test]
     fn tst_cluster_none() {
         log_init();
@@ -126,4 +184,190 @@ mod tests {
         let jets = partons.cluster(anti_kt_f(0.4));
         assert_eq!(jets.len(), 7);
     }
+
+    #[test]
+    fn tst_cluster_ee_kt() {
+        log_init();
+
+        let partons = partons_9_to_7();
+        let sum: PseudoJet =
+            partons.iter().fold(PseudoJet::new(), |s, p| s + *p);
+
+        let jets = partons.cluster(ee_kt_f());
+        assert_eq!(jets.len(), 1);
+        for i in 0..4 {
+            assert!(f64::from(jets[0][i] - sum[i]).abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn tst_cluster_ee_gen_kt() {
+        log_init();
+
+        // the smallest opening angle between any two partons is 0.247
+        let partons = partons_4_to_4();
+
+        let jets = partons.clone().cluster(ee_gen_kt_f(0.2, -1.));
+        assert_eq!(jets.len(), 4);
+
+        let jets = partons.clone().cluster(ee_gen_kt_f(0.3, -1.));
+        assert_eq!(jets.len(), 3);
+
+        let jets = partons.clone().cluster(ee_gen_kt_f(0.3, 0.));
+        assert_eq!(jets.len(), 3);
+
+        // for R > π the beam distance never wins
+        let jets = partons.cluster(ee_gen_kt_f(4., 1.));
+        assert_eq!(jets.len(), 1);
+    }
+
+    #[test]
+    fn tst_cluster_exclusive_n() {
+        log_init();
+
+        let partons = partons_9_to_7();
+        let sum: PseudoJet =
+            partons.iter().fold(PseudoJet::new(), |s, p| s + *p);
+
+        for n in 1..=9 {
+            let jets = partons.clone().cluster_exclusive_n(ee_kt_f(), n);
+            assert_eq!(jets.len(), n);
+            let jet_sum = jets.iter().fold(PseudoJet::new(), |s, p| s + *p);
+            for i in 0..4 {
+                assert!(f64::from(jet_sum[i] - sum[i]).abs() < 1e-10);
+            }
+        }
+
+        let jets = partons.clone().cluster_exclusive_n(kt_f(1.), 3);
+        assert_eq!(jets.len(), 3);
+
+        let jets = partons.clone().cluster_exclusive_n(kt_f(1.), 20);
+        assert_eq!(jets, partons);
+
+        // slices only need to be convertible into pseudojets
+        struct Particle(PseudoJet);
+        impl From<&Particle> for PseudoJet {
+            fn from(p: &Particle) -> Self {
+                p.0
+            }
+        }
+        let particles = Vec::from_iter(partons.iter().map(|&p| Particle(p)));
+        let jets = particles.as_slice().cluster_exclusive_n(kt_f(1.), 3);
+        assert_eq!(jets, partons.clone().cluster_exclusive_n(kt_f(1.), 3));
+    }
+
+    #[test]
+    fn tst_cluster_exclusive_dcut() {
+        log_init();
+
+        let partons = partons_9_to_7();
+
+        let jets = partons.clone().cluster_exclusive_dcut(ee_kt_f(), n64(0.));
+        assert_eq!(jets, partons);
+
+        let jets = partons.clone().cluster_exclusive_dcut(ee_kt_f(), n64(1e10));
+        assert_eq!(jets.len(), 1);
+
+        for dcut in [1e-2, 1., 1e2] {
+            let dcut = n64(dcut);
+            let jets = partons.clone().cluster_exclusive_dcut(ee_kt_f(), dcut);
+            assert!(jets.len() < partons.len());
+            for (i, p1) in jets.iter().enumerate() {
+                for p2 in &jets[i + 1..] {
+                    assert!(ee_kt_f().distance(p1, p2) > dcut);
+                }
+            }
+
+            let jets = partons.clone().cluster_exclusive_dcut(kt_f(0.4), dcut);
+            for (i, p1) in jets.iter().enumerate() {
+                assert!(kt_f(0.4).beam_distance(p1) > dcut);
+                for p2 in &jets[i + 1..] {
+                    assert!(kt_f(0.4).distance(p1, p2) > dcut);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tst_cluster_with_dist() {
+        log_init();
+
+        let d = kt_f(0.4);
+        let history = ClusterHistory::new(partons_9_to_7(), &d);
+        for step in history.with_dist() {
+            match step.step {
+                ClusterStep::Combine([p1, p2]) => {
+                    assert!(!step.is_beam_dist());
+                    assert_eq!(step.dist, d.distance(&p1, &p2));
+                }
+                ClusterStep::Jet(jet) => {
+                    assert!(step.is_beam_dist());
+                    assert_eq!(step.dist, d.beam_distance(&jet));
+                }
+            }
+        }
+
+        let history = ClusterHistory::new(partons_9_to_7(), ee_kt_f());
+        let last = history.with_dist().last().unwrap();
+        assert!(last.is_beam_dist());
+        assert!(last.dist.is_infinite());
+    }
+
+    #[test]
+    fn tst_diff_jet_rates() {
+        log_init();
+
+        let partons = partons_9_to_7();
+        let e_vis = partons.iter().fold(n64(0.), |e, p| e + p.e());
+        let q2 = e_vis * e_vis;
+
+        let rates = partons.clone().diff_jet_rates(ee_kt_f(), q2);
+        assert_eq!(rates.len(), partons.len());
+        assert!(rates[0].is_infinite());
+        for n in 1..partons.len() {
+            assert!(rates[n] <= rates[n - 1]);
+            let ycut = min(rates[n] * 2., (rates[n] + rates[n - 1]) / 2.);
+            let jets =
+                partons.clone().cluster_exclusive_dcut(ee_kt_f(), ycut * q2);
+            assert_eq!(jets.len(), n);
+        }
+    }
+
+    #[test]
+    fn tst_user_index() {
+        log_init();
+
+        let partons = Vec::from_iter(
+            partons_9_to_7()
+                .into_iter()
+                .enumerate()
+                .map(|(n, p)| p.with_user_index(10 * n)),
+        );
+
+        for step in ClusterHistory::new(partons.clone(), anti_kt_f(0.4)) {
+            match step {
+                ClusterStep::Combine([p1, p2]) => {
+                    for p in [p1, p2] {
+                        if let Some(idx) = p.user_index() {
+                            assert_eq!(partons[idx / 10], p);
+                        }
+                    }
+                    assert_eq!((p1 + p2).user_index(), None);
+                }
+                ClusterStep::Jet(jet) => {
+                    if let Some(idx) = jet.user_index() {
+                        assert_eq!(partons[idx / 10], jet);
+                    }
+                }
+            }
+        }
+
+        let seq = ClusterSequence::new(partons.clone(), anti_kt_f(0.4));
+        for &jet in seq.jets() {
+            for idx in seq.constituents(jet) {
+                let constituent = seq.history()[idx].pseudojet;
+                assert_eq!(constituent.user_index(), Some(10 * idx));
+            }
+        }
+    }
 }
@@ -0,0 +1,245 @@
+//! The Lund plane, see [arXiv:1807.04758](https://arxiv.org/abs/1807.04758),
+//! is obtained by reclustering the constituents of a jet with the
+//! Cambridge/Aachen algorithm and undoing the clustering step by
+//! step along the harder branch. Each step corresponds to an
+//! emission characterised by its opening angle Δ and transverse
+//! momentum kt. Following the softer branches instead yields the
+//! secondary Lund planes.
+use noisy_float::prelude::*;
+
+use crate::{groom::Reclustered, PseudoJet};
+
+/// A single step in the declustering of a jet
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LundDeclustering {
+    /// The pseudojet that is declustered
+    pub pair: PseudoJet,
+    /// The branch with larger transverse momentum
+    pub harder: PseudoJet,
+    /// The branch with smaller transverse momentum
+    pub softer: PseudoJet,
+    /// Invariant mass of `pair`
+    pub m: N64,
+    /// Distance Δ in the rapidity-azimuth plane between the branches
+    pub delta: N64,
+    /// Transverse momentum fraction z of the softer branch
+    pub z: N64,
+    /// Transverse momentum kt = z Δ (pt_harder + pt_softer) of the
+    /// softer branch relative to the harder one
+    pub kt: N64,
+    /// κ = z Δ
+    pub kappa: N64,
+    /// Azimuthal angle ψ of the softer branch around the harder one
+    pub psi: N64,
+    /// Declusterings of the softer branch
+    ///
+    /// This is only filled for primary declusterings by a
+    /// [LundGenerator] with secondary planes enabled.
+    pub secondary: Vec<LundDeclustering>,
+}
+
+impl LundDeclustering {
+    fn new(pair: PseudoJet, harder: PseudoJet, softer: PseudoJet) -> Self {
+        let delta = harder.delta_r(&softer);
+        let z = softer.pt() / (harder.pt() + softer.pt());
+        Self {
+            m: pair.m(),
+            delta,
+            z,
+            kt: softer.pt() * delta,
+            kappa: z * delta,
+            psi: softer.delta_rap(&harder).atan2(softer.delta_phi(&harder)),
+            pair,
+            harder,
+            softer,
+            secondary: Vec::new(),
+        }
+    }
+
+    /// Logarithm ln(1/Δ) of the inverse opening angle
+    pub fn ln_inv_delta(&self) -> N64 {
+        -self.delta.ln()
+    }
+
+    /// Logarithm ln(kt) of the relative transverse momentum
+    pub fn ln_kt(&self) -> N64 {
+        self.kt.ln()
+    }
+}
+
+/// Generator for the declusterings in the Lund plane of a jet
+///
+/// # Examples
+///
+/// ```rust
+/// use jetty::{lund::LundGenerator, pseudojet_f};
+///
+/// let constituents = [
+///     pseudojet_f(0.2626773221934335, -0.08809521946454194, -0.1141608706693822, -0.2195584284654444),
+///     pseudojet_f(2.21902459329915, -0.7529973704809976, -0.9658189214109036, -1.850475321845671)
+/// ];
+///
+/// let declusterings = LundGenerator::new().generate(&constituents);
+/// assert_eq!(declusterings.len(), 1);
+/// assert!(declusterings[0].ln_inv_delta() > 4.);
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LundGenerator {
+    /// Whether to also decluster the softer branches of the primary
+    /// declusterings
+    pub secondary: bool,
+}
+
+impl LundGenerator {
+    /// Generator for the primary Lund plane
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generator for the primary and secondary Lund planes
+    pub fn with_secondary() -> Self {
+        Self { secondary: true }
+    }
+
+    /// Primary declusterings of the jet with the given constituents,
+    /// starting with the widest one
+    pub fn generate(
+        &self,
+        constituents: &[PseudoJet],
+    ) -> Vec<LundDeclustering> {
+        match Reclustered::new(constituents) {
+            Some(jet) => self.decluster(&jet, jet.jet(), self.secondary),
+            None => Vec::new(),
+        }
+    }
+
+    // Declusterings along the harder branch starting from history
+    // index `idx`
+    fn decluster(
+        &self,
+        jet: &Reclustered,
+        mut idx: usize,
+        secondary: bool,
+    ) -> Vec<LundDeclustering> {
+        let mut res = Vec::new();
+        while let Some(split) = jet.split(idx) {
+            let mut declustering = LundDeclustering::new(
+                jet.pseudojet(idx),
+                jet.pseudojet(split.harder),
+                jet.pseudojet(split.softer),
+            );
+            if secondary {
+                declustering.secondary =
+                    self.decluster(jet, split.softer, false);
+            }
+            res.push(declustering);
+            idx = split.harder;
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_data::*;
+
+    use super::*;
+
+    fn assert_close(a: N64, b: f64) {
+        assert!(f64::from((a - b).abs()) < 1e-10, "{a} != {b}");
+    }
+
+    fn massless(pt: f64, y: f64, phi: f64) -> PseudoJet {
+        PseudoJet::from_pt_y_phi_m(n64(pt), n64(y), n64(phi), n64(0.))
+    }
+
+    #[test]
+    fn tst_lund_2_to_1() {
+        let partons = partons_2_to_1();
+        let lund = LundGenerator::new().generate(&partons);
+        assert_eq!(lund.len(), 1);
+        let d = &lund[0];
+        assert_eq!(d.harder, partons[1]);
+        assert_eq!(d.softer, partons[0]);
+        assert_close(d.delta, 0.008095547380574799);
+        assert_close(d.ln_inv_delta(), 4.816441074549412);
+        assert_close(d.kt, 0.0011673732187884108);
+        assert_close(d.ln_kt(), -6.752999166361953);
+        assert_close(d.z, 0.10534215118030102);
+        assert_close(d.kappa, 0.0008528023760518004);
+        assert_close(d.psi, -0.9087847312812998);
+        assert_close(d.m, 0.003402024306495988);
+    }
+
+    #[test]
+    fn tst_lund_3_to_2() {
+        let partons = partons_3_to_2();
+        let lund = LundGenerator::with_secondary().generate(&partons);
+        assert_eq!(lund.len(), 2);
+
+        let d = &lund[0];
+        assert_eq!(d.softer, partons[2]);
+        assert_close(d.delta, 2.241070651194547);
+        assert_close(d.ln_kt(), 1.4147434863511439);
+        assert_close(d.z, 0.14724713283202884);
+        assert_close(d.kappa, 0.3299912278624048);
+        assert_close(d.psi, 2.6529360378754765);
+        assert_close(d.m, 9.093906137049904);
+        assert!(d.secondary.is_empty());
+
+        let d = &lund[1];
+        assert_eq!([d.harder, d.softer], [partons[1], partons[0]]);
+        assert_close(d.delta, 0.3699507323773226);
+        assert_close(d.ln_kt(), 0.2805357910961171);
+        assert_close(d.z, 0.331597352189058);
+        assert_close(d.kappa, 0.122674683296723);
+        assert_close(d.psi, -0.2022660745019112);
+        assert_close(d.m, 1.8696917717405752);
+    }
+
+    #[test]
+    fn tst_lund_secondary() {
+        let hard = [massless(100., 0., 1.), massless(30., 0.05, 1.05)];
+        let soft = [massless(20., 0.6, 1.), massless(5., 0.62, 0.97)];
+        let constituents = [hard, soft].concat();
+
+        let primary = LundGenerator::new().generate(&constituents);
+        let lund = LundGenerator::with_secondary().generate(&constituents);
+        assert_eq!(lund.len(), 2);
+        assert_eq!(lund[0].softer, soft[0] + soft[1]);
+        assert_eq!(lund[0].secondary, LundGenerator::new().generate(&soft));
+        assert_eq!(lund[0].secondary.len(), 1);
+        assert_eq!(lund[1].secondary, []);
+        for (with_secondary, primary) in lund.into_iter().zip(primary) {
+            assert_eq!(
+                LundDeclustering {
+                    secondary: Vec::new(),
+                    ..with_secondary
+                },
+                primary
+            );
+        }
+
+        assert!(LundGenerator::new().generate(&[]).is_empty());
+    }
+
+    #[test]
+    fn tst_lund_wide() {
+        let (prongs, forward) = wide_jet();
+        let constituents = [&prongs[0][..], &prongs[1], &[forward]].concat();
+
+        let lund = LundGenerator::with_secondary().generate(&constituents);
+        assert!(lund.len() > 2);
+        assert_eq!(lund[0].softer, forward);
+        assert!(lund[0].delta > 5.);
+        assert!(lund[0].secondary.is_empty());
+
+        let d = &lund[1];
+        assert!((d.z - 240. / 540.).abs() < 1e-10);
+        assert!((d.delta - 2.).abs() < 1e-3);
+        assert!(!d.secondary.is_empty());
+        // the remaining declusterings are within the prongs
+        let mut within_prongs = lund[2..].iter().chain(&d.secondary);
+        assert!(within_prongs.all(|d| d.delta < 0.11));
+    }
+}
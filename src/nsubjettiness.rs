@@ -0,0 +1,339 @@
+//! N-subjettiness, see
+//! [arXiv:1011.2268](https://arxiv.org/abs/1011.2268), measures how
+//! well the constituents of a jet are described by N subjet
+//! axes. With transverse momenta pt_k and distances ΔR_jk between
+//! constituent k and axis j, it is defined as
+//!
+//! τ_N = Σ_k pt_k min_j ΔR_jk^β,
+//!
+//! optionally divided by a normalisation factor. Small ratios
+//! τ_N/τ_{N-1} indicate jets with N prongs, for example
+//! τ_21 = τ_2/τ_1 for hadronically decaying W bosons and τ_32 =
+//! τ_3/τ_2 for top quarks.
+use noisy_float::prelude::*;
+
+use crate::{
+    cambridge_aachen, cluster::ClusterHistory, groom::WithoutBeam, kt,
+    recombine::WtaPtScheme, ClusterExt, PseudoJet,
+};
+
+// Maximum number of iterations in the one-pass minimisation
+const MAX_ITERATIONS: usize = 100;
+// The one-pass minimisation stops once no axis moves by more than
+// this distance in the rapidity-azimuth plane
+const AXES_TOLERANCE: f64 = 1e-8;
+
+/// Choice of subjet axes
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Axes {
+    /// Exclusive kt subjets
+    #[default]
+    ExclusiveKt,
+    /// Exclusive Cambridge/Aachen subjets
+    ExclusiveCambridgeAachen,
+    /// Exclusive kt subjets with the winner-takes-all recombination
+    /// scheme, see [arXiv:1401.2158](https://arxiv.org/abs/1401.2158)
+    WtaKt,
+    /// Exclusive kt subjets, followed by an iterative minimisation
+    /// of τ_N, see [arXiv:1108.2701](https://arxiv.org/abs/1108.2701)
+    OnePassKt,
+}
+
+/// Normalisation of N-subjettiness
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Normalisation {
+    /// No normalisation
+    #[default]
+    Unnormalised,
+    /// Divide by d_0 = Σ_k pt_k `r0`^β, where `r0` is usually the
+    /// jet radius
+    Normalised {
+        /// Characteristic jet radius
+        r0: N64,
+    },
+}
+
+/// N-subjettiness calculator
+///
+/// # Examples
+///
+/// ```rust
+/// use jetty::{nsubjettiness::{Axes, Nsubjettiness}, pseudojet_f};
+/// use noisy_float::prelude::*;
+///
+/// let constituents = [
+///     pseudojet_f(0.2626773221934335, -0.08809521946454194, -0.1141608706693822, -0.2195584284654444),
+///     pseudojet_f(2.21902459329915, -0.7529973704809976, -0.9658189214109036, -1.850475321845671)
+/// ];
+///
+/// let nsubjettiness = Nsubjettiness::new(n64(1.), Axes::WtaKt);
+/// assert!(nsubjettiness.tau(&constituents, 1) > 0.);
+/// assert_eq!(nsubjettiness.tau(&constituents, 2), 0.);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Nsubjettiness {
+    /// Angular exponent β
+    pub beta: N64,
+    /// Choice of subjet axes
+    pub axes: Axes,
+    /// Normalisation
+    pub normalisation: Normalisation,
+}
+
+impl Nsubjettiness {
+    /// Unnormalised N-subjettiness with angular exponent `beta` and
+    /// the given choice of axes
+    pub fn new(beta: N64, axes: Axes) -> Self {
+        assert!(beta > 0.);
+        Self {
+            beta,
+            axes,
+            normalisation: Normalisation::Unnormalised,
+        }
+    }
+
+    /// N-subjettiness normalised to d_0 = Σ_k pt_k `r0`^β
+    pub fn normalised(beta: N64, axes: Axes, r0: N64) -> Self {
+        assert!(r0 > 0.);
+        Self {
+            normalisation: Normalisation::Normalised { r0 },
+            ..Self::new(beta, axes)
+        }
+    }
+
+    /// N-subjettiness τ_N of the jet with the given constituents
+    ///
+    /// If there are at most `n` constituents, τ_N vanishes.
+    pub fn tau(&self, constituents: &[PseudoJet], n: usize) -> N64 {
+        self.tau_with_axes(constituents, &self.axes(constituents, n))
+    }
+
+    /// N-subjettiness with respect to the given axes
+    pub fn tau_with_axes(
+        &self,
+        constituents: &[PseudoJet],
+        axes: &[PseudoJet],
+    ) -> N64 {
+        let tau = constituents
+            .iter()
+            .map(|c| c.pt() * self.min_distance(c, axes))
+            .sum();
+        match self.normalisation {
+            Normalisation::Unnormalised => tau,
+            Normalisation::Normalised { r0 } => {
+                let d0: N64 = constituents.iter().map(|c| c.pt()).sum();
+                let d0 = d0 * r0.powf(self.beta);
+                if d0 > 0. {
+                    tau / d0
+                } else {
+                    n64(0.)
+                }
+            }
+        }
+    }
+
+    /// The ratio τ_N/τ_{N-1}
+    ///
+    /// If τ_{N-1} vanishes, the ratio is set to zero.
+    pub fn ratio(&self, constituents: &[PseudoJet], n: usize) -> N64 {
+        assert!(n > 1);
+        let denom = self.tau(constituents, n - 1);
+        if denom > 0. {
+            self.tau(constituents, n) / denom
+        } else {
+            n64(0.)
+        }
+    }
+
+    /// The ratio τ_21 = τ_2/τ_1
+    pub fn tau21(&self, constituents: &[PseudoJet]) -> N64 {
+        self.ratio(constituents, 2)
+    }
+
+    /// The ratio τ_32 = τ_3/τ_2
+    pub fn tau32(&self, constituents: &[PseudoJet]) -> N64 {
+        self.ratio(constituents, 3)
+    }
+
+    /// The `n` subjet axes for the jet with the given constituents
+    ///
+    /// If there are at most `n` constituents, they are returned
+    /// as axes.
+    pub fn axes(&self, constituents: &[PseudoJet], n: usize) -> Vec<PseudoJet> {
+        assert!(n > 0);
+        let constituents_vec = constituents.to_vec();
+        let kt = WithoutBeam(kt(n64(1.)));
+        match self.axes {
+            Axes::ExclusiveKt => constituents_vec.cluster_exclusive_n(kt, n),
+            Axes::ExclusiveCambridgeAachen => constituents_vec
+                .cluster_exclusive_n(WithoutBeam(cambridge_aachen(n64(1.))), n),
+            Axes::WtaKt => ClusterHistory::with_recombiner(
+                constituents_vec,
+                kt,
+                WtaPtScheme,
+            )
+            .exclusive_n(n),
+            Axes::OnePassKt => {
+                let axes = constituents_vec.cluster_exclusive_n(kt, n);
+                self.minimise(constituents, axes)
+            }
+        }
+    }
+
+    // Iteratively move each axis to the point minimising the
+    // contribution to τ_N of the constituents closest to it
+    //
+    // For β = 2 this is the pt-weighted centroid, otherwise each
+    // iteration is a step of the generalised Weiszfeld algorithm.
+    fn minimise(
+        &self,
+        constituents: &[PseudoJet],
+        mut axes: Vec<PseudoJet>,
+    ) -> Vec<PseudoJet> {
+        for _ in 0..MAX_ITERATIONS {
+            let mut sums = vec![[n64(0.); 3]; axes.len()];
+            for c in constituents {
+                let Some((j, axis)) = axes
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, axis)| c.delta_r2(axis))
+                else {
+                    return axes;
+                };
+                let delta_r = c.delta_r(axis);
+                // the Weiszfeld step is undefined for constituents
+                // exactly on the axis
+                if delta_r == 0. && self.beta < 2. {
+                    continue;
+                }
+                let weight = c.pt() * delta_r.powf(self.beta - 2.);
+                sums[j][0] += weight;
+                sums[j][1] += weight * c.delta_rap(axis);
+                sums[j][2] += weight * c.delta_phi(axis);
+            }
+            let mut converged = true;
+            for (axis, [weight, rap, phi]) in axes.iter_mut().zip(sums) {
+                if weight == 0. {
+                    continue;
+                }
+                let [rap, phi] = [rap / weight, phi / weight];
+                if rap * rap + phi * phi > AXES_TOLERANCE * AXES_TOLERANCE {
+                    converged = false;
+                }
+                *axis = PseudoJet::from_pt_y_phi_m(
+                    axis.pt(),
+                    axis.rap() + rap,
+                    axis.phi() + phi,
+                    n64(0.),
+                );
+            }
+            if converged {
+                break;
+            }
+        }
+        axes
+    }
+
+    // min_j ΔR_jk^β
+    fn min_distance(&self, constituent: &PseudoJet, axes: &[PseudoJet]) -> N64 {
+        let Some(delta_r2) = axes.iter().map(|a| constituent.delta_r2(a)).min()
+        else {
+            return n64(0.);
+        };
+        delta_r2.powf(self.beta / 2.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_data::*;
+
+    use super::*;
+
+    fn assert_close(a: N64, b: f64) {
+        assert!(f64::from((a - b).abs()) < 1e-10, "{a} != {b}");
+    }
+
+    #[test]
+    fn tst_nsubjettiness_exclusive() {
+        let partons = partons_3_to_2();
+        for axes in [Axes::ExclusiveKt, Axes::ExclusiveCambridgeAachen] {
+            let tau = Nsubjettiness::new(n64(1.), axes);
+            assert_close(tau.tau(&partons, 1), 6.4693779054803535);
+            assert_close(tau.tau(&partons, 2), 1.7675874039469157);
+            assert_eq!(tau.tau(&partons, 3), 0.);
+            assert_eq!(tau.tau(&partons, 4), 0.);
+            assert_close(tau.tau21(&partons), 0.27322370555127923);
+            assert_eq!(tau.tau32(&partons), 0.);
+
+            let tau = Nsubjettiness::new(n64(2.), axes);
+            assert_close(tau.tau(&partons, 1), 8.39418869490416);
+            assert_close(tau.tau(&partons, 2), 0.32735752735650175);
+        }
+    }
+
+    #[test]
+    fn tst_nsubjettiness_wta() {
+        let partons = partons_3_to_2();
+        let tau = Nsubjettiness::new(n64(1.), Axes::WtaKt);
+        // the axes are aligned with the hardest constituents
+        let axes = tau.axes(&partons, 2);
+        assert_eq!(axes.len(), 2);
+        assert!(axes.iter().any(|a| a.delta_r(&partons[1]) < 1e-10));
+        assert!(axes.iter().any(|a| a.delta_r(&partons[2]) < 1e-10));
+        assert_close(tau.tau(&partons, 1), 5.22464627715751);
+        assert_close(tau.tau(&partons, 2), 1.3238389234606436);
+    }
+
+    #[test]
+    fn tst_nsubjettiness_one_pass() {
+        let partons = partons_3_to_2();
+        // for β = 2 the optimal axis is the pt-weighted centroid
+        let tau = Nsubjettiness::new(n64(2.), Axes::OnePassKt);
+        assert_close(tau.tau(&partons, 1), 8.212800890484122);
+
+        let partons = partons_9_to_7();
+        for beta in [0.5, 1., 2.] {
+            let one_pass = Nsubjettiness::new(n64(beta), Axes::OnePassKt);
+            let kt = Nsubjettiness::new(n64(beta), Axes::ExclusiveKt);
+            for n in 1..=3 {
+                let tau = one_pass.tau(&partons, n);
+                assert!(tau <= kt.tau(&partons, n) * (1. + 1e-10));
+            }
+        }
+    }
+
+    #[test]
+    fn tst_nsubjettiness_normalised() {
+        let partons = partons_3_to_2();
+        let r0 = n64(0.8);
+        let tau = Nsubjettiness::normalised(n64(1.), Axes::ExclusiveKt, r0);
+        let d0: N64 = partons.iter().map(|p| p.pt() * r0).sum();
+        assert_close(tau.tau(&partons, 1), 6.4693779054803535 / f64::from(d0));
+        // ratios are independent of the normalisation
+        assert_close(tau.tau21(&partons), 0.27322370555127923);
+        assert_eq!(tau.tau(&[], 1), 0.);
+    }
+
+    #[test]
+    fn tst_nsubjettiness_wide() {
+        let (prongs, _) = wide_jet();
+        let constituents = prongs.concat();
+        let jet: PseudoJet = constituents.iter().sum();
+        let prong_axes = prongs.map(|p| p.iter().sum::<PseudoJet>());
+
+        for axes in [Axes::ExclusiveKt, Axes::ExclusiveCambridgeAachen] {
+            let tau = Nsubjettiness::new(n64(1.), axes);
+            let tau1 = tau.tau(&constituents, 1);
+            assert_close(tau1, tau.tau_with_axes(&constituents, &[jet]).into());
+            let tau2 = tau.tau_with_axes(&constituents, &prong_axes);
+            assert_close(tau.tau(&constituents, 2), tau2.into());
+            assert_close(tau.tau21(&constituents), (tau2 / tau1).into());
+            assert!(tau.tau21(&constituents) < 0.1);
+        }
+        for axes in [Axes::WtaKt, Axes::OnePassKt] {
+            let tau = Nsubjettiness::new(n64(1.), axes);
+            assert!(tau.tau21(&constituents) < 0.1);
+        }
+    }
+}
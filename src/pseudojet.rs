@@ -1,19 +1,40 @@
-use std::convert::From;
+use std::cmp::{max, min};
+use std::convert::{From, TryFrom};
 use std::default::Default;
 use std::f64::consts::PI;
-use std::ops::{Add, AddAssign, Index, Sub, SubAssign};
+use std::iter::Sum;
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Index, Mul, MulAssign, Neg, Sub, SubAssign,
+};
 
 use noisy_float::prelude::*;
 
+use crate::Error;
+
 pub const D: usize = 4;
 
+/// Relative tolerance for energies below the absolute three-momentum
+///
+/// Momenta with `E < |p|` are only considered unphysical if the
+/// difference exceeds this fraction of `|p|`, so that rounding errors
+/// in massless momenta are accepted.
+pub const SPACELIKE_TOLERANCE: f64 = 1e-8;
+
+const MAX_RAP: f64 = 1e5;
+
 /// A pseudojet is a particle momentum or a sum of momenta of clustered particles
+///
+/// Input particles can be labelled with a user index, for example
+/// pointing to additional information like the particle type.
+/// Pseudojets obtained by adding or subtracting other pseudojets do
+/// not have a user index.
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct PseudoJet {
     comp: [N64; D],
     inv_pt2: N64,
     phi: N64,
     rap: N64,
+    user_index: Option<usize>,
 }
 
 impl PseudoJet {
@@ -22,11 +43,126 @@ impl PseudoJet {
         Self::default()
     }
 
+    /// Create pseudojet from the four-momentum components, checking
+    /// that they describe a physical momentum
+    ///
+    /// Returns an error if any component is NaN or infinite or if the
+    /// energy is smaller than the absolute three-momentum beyond
+    /// [SPACELIKE_TOLERANCE].
+    pub fn try_new(e: f64, px: f64, py: f64, pz: f64) -> Result<Self, Error> {
+        check_momentum([e, px, py, pz])?;
+        Ok(pseudojet_f(e, px, py, pz))
+    }
+
+    /// Check that the pseudojet has a finite, physical four-momentum
+    ///
+    /// See [PseudoJet::try_new] for the criteria.
+    pub fn check(&self) -> Result<(), Error> {
+        check_momentum(self.comp.map(f64::from))
+    }
+
+    /// Create pseudojet from transverse momentum, rapidity, azimuthal
+    /// angle, and mass
+    pub fn from_pt_y_phi_m(pt: N64, y: N64, phi: N64, m: N64) -> Self {
+        let mt = (pt * pt + m * m).sqrt();
+        Self::from_pt_phi_e_pz(pt, phi, mt * y.cosh(), mt * y.sinh())
+    }
+
+    /// Create pseudojet from transverse momentum, rapidity, azimuthal
+    /// angle, and energy
+    pub fn from_pt_y_phi_e(pt: N64, y: N64, phi: N64, e: N64) -> Self {
+        Self::from_pt_phi_e_pz(pt, phi, e, e * y.tanh())
+    }
+
+    /// Create pseudojet from transverse momentum, pseudorapidity,
+    /// azimuthal angle, and mass
+    pub fn from_pt_eta_phi_m(pt: N64, eta: N64, phi: N64, m: N64) -> Self {
+        let pz = pt * eta.sinh();
+        let e = (pt * pt + pz * pz + m * m).sqrt();
+        Self::from_pt_phi_e_pz(pt, phi, e, pz)
+    }
+
+    /// Create pseudojet from transverse momentum, pseudorapidity,
+    /// azimuthal angle, and energy
+    pub fn from_pt_eta_phi_e(pt: N64, eta: N64, phi: N64, e: N64) -> Self {
+        Self::from_pt_phi_e_pz(pt, phi, e, pt * eta.sinh())
+    }
+
+    // the cached pt2, phi, and rapidity are always recomputed from the
+    // components, so that equal momenta compare equal
+    fn from_pt_phi_e_pz(pt: N64, phi: N64, e: N64, pz: N64) -> Self {
+        pseudojet(e, pt * phi.cos(), pt * phi.sin(), pz)
+    }
+
+    /// User index
+    pub fn user_index(&self) -> Option<usize> {
+        self.user_index
+    }
+
+    /// Set the user index
+    pub fn set_user_index(&mut self, user_index: usize) {
+        self.user_index = Some(user_index)
+    }
+
+    /// Remove the user index
+    pub fn reset_user_index(&mut self) {
+        self.user_index = None
+    }
+
+    /// Return a copy with the given user index
+    pub fn with_user_index(mut self, user_index: usize) -> Self {
+        self.set_user_index(user_index);
+        self
+    }
+
     /// Energy
     pub fn e(&self) -> N64 {
         self[0]
     }
 
+    /// Set the energy
+    pub fn set_e(&mut self, e: N64) {
+        self.set_comp(0, e)
+    }
+
+    /// Set the momentum in x direction
+    pub fn set_px(&mut self, px: N64) {
+        self.set_comp(1, px)
+    }
+
+    /// Set the momentum in y direction
+    pub fn set_py(&mut self, py: N64) {
+        self.set_comp(2, py)
+    }
+
+    /// Set the momentum in z direction
+    pub fn set_pz(&mut self, pz: N64) {
+        self.set_comp(3, pz)
+    }
+
+    /// Set the four-momentum component with index `i`
+    ///
+    /// The components are ordered as (E, px, py, pz). The user index
+    /// is kept.
+    pub fn set_comp(&mut self, i: usize, value: N64) {
+        self.comp[i] = value;
+        self.init_pt2_phi_rap()
+    }
+
+    /// Set all four-momentum components, keeping the user index
+    pub fn reset_momentum(&mut self, e: N64, px: N64, py: N64, pz: N64) {
+        self.comp = [e, px, py, pz];
+        self.init_pt2_phi_rap()
+    }
+
+    /// Minkowski product `e*e' - px*px' - py*py' - pz*pz'`
+    pub fn dot(&self, other: &PseudoJet) -> N64 {
+        self.e() * other.e()
+            - self.px() * other.px()
+            - self.py() * other.py()
+            - self.pz() * other.pz()
+    }
+
     /// Momentum in x direction
     pub fn px(&self) -> N64 {
         self[1]
@@ -48,6 +184,10 @@ impl PseudoJet {
     }
 
     /// Rapidity y
+    ///
+    /// For massless momenta along the beam axis this is
+    /// ±(10^5 + |pz|). For space-like momenta, the rapidity is the
+    /// same as for a massless momentum with the same pt and pz.
     pub fn rap(&self) -> N64 {
         self.rap
     }
@@ -77,6 +217,115 @@ impl PseudoJet {
         self.pt2().sqrt()
     }
 
+    /// Square of the three-momentum `modp2 = px*px + py*py + pz*pz`
+    pub fn modp2(&self) -> N64 {
+        self.pt2() + self.pz() * self.pz()
+    }
+
+    /// Absolute value of the three-momentum `modp = (px*px + py*py + pz*pz)^(1/2)`
+    pub fn modp(&self) -> N64 {
+        self.modp2().sqrt()
+    }
+
+    /// Squared invariant mass `m2 = e*e - px*px - py*py - pz*pz`
+    ///
+    /// This is negative for space-like momenta.
+    pub fn m2(&self) -> N64 {
+        // more precise than e*e - modp2 for e ≈ |pz|
+        (self.e() + self.pz()) * (self.e() - self.pz()) - self.pt2()
+    }
+
+    /// Invariant mass `m = m2^(1/2)`
+    ///
+    /// For space-like momenta with negative `m2` this returns
+    /// `-(-m2)^(1/2)`.
+    pub fn m(&self) -> N64 {
+        signed_sqrt(self.m2())
+    }
+
+    /// Squared transverse mass `mt2 = m2 + pt2 = e*e - pz*pz`
+    pub fn mt2(&self) -> N64 {
+        (self.e() + self.pz()) * (self.e() - self.pz())
+    }
+
+    /// Transverse mass `mt = mt2^(1/2)`
+    ///
+    /// For negative `mt2` this returns `-(-mt2)^(1/2)`.
+    pub fn mt(&self) -> N64 {
+        signed_sqrt(self.mt2())
+    }
+
+    /// Squared transverse energy `et2 = e*e * pt2 / modp2`
+    ///
+    /// This is zero for vanishing three-momentum.
+    pub fn et2(&self) -> N64 {
+        let modp2 = self.modp2();
+        if modp2 == 0. {
+            n64(0.)
+        } else {
+            self.e() * self.e() * self.pt2() / modp2
+        }
+    }
+
+    /// Transverse energy `et = e * pt / modp`
+    ///
+    /// This is zero for vanishing three-momentum.
+    pub fn et(&self) -> N64 {
+        let modp = self.modp();
+        if modp == 0. {
+            n64(0.)
+        } else {
+            self.e() * self.pt() / modp
+        }
+    }
+
+    /// Pseudorapidity η = -ln(tan(θ/2))
+    ///
+    /// For momenta along the beam axis this is ±(10^5 + |pz|), like
+    /// the rapidity. For vanishing three-momentum it is zero.
+    pub fn eta(&self) -> N64 {
+        let pt = self.pt();
+        let pz = self.pz();
+        if pt == 0. {
+            if pz == 0. {
+                n64(0.)
+            } else {
+                on_axis_rap(pz)
+            }
+        } else {
+            (pz / pt).asinh()
+        }
+    }
+
+    /// Pseudorapidity η = -ln(tan(θ/2))
+    pub fn pseudorapidity(&self) -> N64 {
+        self.eta()
+    }
+
+    /// Polar angle θ with respect to the beam axis
+    ///
+    /// The angle is in the range 0 <= θ <= π and zero for vanishing
+    /// three-momentum.
+    pub fn theta(&self) -> N64 {
+        self.pt().atan2(self.pz())
+    }
+
+    /// Velocity β = modp / e
+    ///
+    /// This is zero for vanishing energy.
+    pub fn beta(&self) -> N64 {
+        if self.e() == 0. {
+            n64(0.)
+        } else {
+            self.modp() / self.e()
+        }
+    }
+
+    /// kt distance min(pt2_i, pt2_j) ΔR^2 to another pseudojet
+    pub fn kt_distance(&self, p: &PseudoJet) -> N64 {
+        min(self.pt2(), p.pt2()) * self.delta_r2(p)
+    }
+
     /// Calculate ΔR^2 = Δφ^2 + Δy^2
     pub fn delta_r2(&self, p: &PseudoJet) -> N64 {
         self.delta_phi2(p) + self.delta_rap2(p)
@@ -150,14 +399,61 @@ impl PseudoJet {
             self.phi -= n64(2.) * PI;
         }
 
+        // treat negative m2 as zero to avoid NaN
+        let m2 = max((e + pz) * (e - pz) - pt2, n64(0.));
         self.rap = if e == 0. && pz == 0. {
             n64(0.)
+        } else if pt2 + m2 == 0. {
+            on_axis_rap(pz)
         } else {
-            ((e + pz) / (e - pz)).ln() / 2.
+            let e_plus_abs_pz = e + pz.abs();
+            let rap = ((pt2 + m2) / (e_plus_abs_pz * e_plus_abs_pz)).ln() / 2.;
+            if pz > 0. {
+                -rap
+            } else {
+                rap
+            }
         }
     }
 }
 
+// (pseudo)rapidity assigned to momenta along the beam axis
+//
+// Following fastjet, this is large, but finite, to avoid NaN in
+// rapidity differences.
+fn on_axis_rap(pz: N64) -> N64 {
+    let rap = pz.abs() + MAX_RAP;
+    if pz >= 0. {
+        rap
+    } else {
+        -rap
+    }
+}
+
+// square root preserving the sign of the argument
+fn signed_sqrt(x: N64) -> N64 {
+    if x < 0. {
+        -(-x).sqrt()
+    } else {
+        x.sqrt()
+    }
+}
+
+fn check_momentum(comp: [f64; D]) -> Result<(), Error> {
+    if let Some(i) = comp.iter().position(|c| c.is_nan()) {
+        return Err(Error::NaN(i));
+    }
+    if let Some(i) = comp.iter().position(|c| c.is_infinite()) {
+        return Err(Error::Infinite(i));
+    }
+    let [e, px, py, pz] = comp;
+    let modp = (px * px + py * py + pz * pz).sqrt();
+    if modp - e > SPACELIKE_TOLERANCE * modp {
+        return Err(Error::Spacelike { e, modp });
+    }
+    Ok(())
+}
+
 macro_rules! forward_ref_from {
     ($from:ty, $to:ty) => {
         impl From<&$from> for $to {
@@ -213,12 +509,77 @@ impl From<(f64, f64, f64, f64)> for PseudoJet {
 
 forward_ref_from!((f64, f64, f64, f64), PseudoJet);
 
+/// Create a pseudojet from the four-momentum components, checking
+/// that they describe a physical momentum
+///
+/// See [PseudoJet::try_new] for details.
+impl TryFrom<&[f64]> for PseudoJet {
+    type Error = Error;
+
+    fn try_from(comp: &[f64]) -> Result<Self, Self::Error> {
+        match *comp {
+            [e, px, py, pz] => Self::try_new(e, px, py, pz),
+            _ => Err(Error::WrongLength(comp.len())),
+        }
+    }
+}
+
+/// Conversion into a pseudojet that checks for a physical
+/// four-momentum
+///
+/// In contrast to converting with [Into] and calling
+/// [PseudoJet::check] afterwards, this never panics for NaN
+/// components.
+pub trait TryIntoPseudoJet {
+    /// Convert into a pseudojet
+    ///
+    /// See [PseudoJet::try_new] for the checks.
+    fn try_into_pseudojet(&self) -> Result<PseudoJet, Error>;
+}
+
+impl TryIntoPseudoJet for PseudoJet {
+    fn try_into_pseudojet(&self) -> Result<PseudoJet, Error> {
+        self.check()?;
+        Ok(*self)
+    }
+}
+
+impl TryIntoPseudoJet for [f64; D] {
+    fn try_into_pseudojet(&self) -> Result<PseudoJet, Error> {
+        PseudoJet::try_from(self.as_slice())
+    }
+}
+
+impl TryIntoPseudoJet for [N64; D] {
+    fn try_into_pseudojet(&self) -> Result<PseudoJet, Error> {
+        self.map(f64::from).try_into_pseudojet()
+    }
+}
+
+impl TryIntoPseudoJet for (f64, f64, f64, f64) {
+    fn try_into_pseudojet(&self) -> Result<PseudoJet, Error> {
+        let &(e, px, py, pz) = self;
+        [e, px, py, pz].try_into_pseudojet()
+    }
+}
+
+impl TryIntoPseudoJet for (N64, N64, N64, N64) {
+    fn try_into_pseudojet(&self) -> Result<PseudoJet, Error> {
+        let &(e, px, py, pz) = self;
+        [e, px, py, pz].try_into_pseudojet()
+    }
+}
+
 /// Create a pseudojet from the four-momentum components
 pub fn pseudojet(e: N64, px: N64, py: N64, pz: N64) -> PseudoJet {
     [e, px, py, pz].into()
 }
 
 /// Create a pseudojet from the four-momentum components
+///
+/// NaN components only cause a panic if debug assertions are
+/// enabled and are otherwise silently accepted. Use
+/// [PseudoJet::try_new] to check the input instead.
 pub fn pseudojet_f(e: f64, px: f64, py: f64, pz: f64) -> PseudoJet {
     pseudojet(n64(e), n64(px), n64(py), n64(pz))
 }
@@ -230,6 +591,7 @@ impl Default for PseudoJet {
             inv_pt2: n64(f64::INFINITY),
             phi: Default::default(),
             rap: Default::default(),
+            user_index: None,
         }
     }
 }
@@ -247,6 +609,7 @@ impl AddAssign for PseudoJet {
         for i in 0..D {
             self.comp[i] += other.comp[i]
         }
+        self.user_index = None;
         self.init_pt2_phi_rap()
     }
 }
@@ -265,6 +628,7 @@ impl SubAssign for PseudoJet {
         for i in 0..D {
             self.comp[i] -= other.comp[i]
         }
+        self.user_index = None;
         self.init_pt2_phi_rap()
     }
 }
@@ -277,3 +641,430 @@ impl Sub for PseudoJet {
         self
     }
 }
+
+/// Rescale the four-momentum, keeping the user index
+impl MulAssign<N64> for PseudoJet {
+    fn mul_assign(&mut self, factor: N64) {
+        for i in 0..D {
+            self.comp[i] *= factor
+        }
+        self.init_pt2_phi_rap()
+    }
+}
+
+/// Rescale the four-momentum, keeping the user index
+impl Mul<N64> for PseudoJet {
+    type Output = Self;
+
+    fn mul(mut self, factor: N64) -> Self::Output {
+        self *= factor;
+        self
+    }
+}
+
+/// Rescale the four-momentum, keeping the user index
+impl Mul<PseudoJet> for N64 {
+    type Output = PseudoJet;
+
+    fn mul(self, p: PseudoJet) -> Self::Output {
+        p * self
+    }
+}
+
+/// Rescale the four-momentum, keeping the user index
+impl DivAssign<N64> for PseudoJet {
+    fn div_assign(&mut self, divisor: N64) {
+        *self *= n64(1.) / divisor
+    }
+}
+
+/// Rescale the four-momentum, keeping the user index
+impl Div<N64> for PseudoJet {
+    type Output = Self;
+
+    fn div(mut self, divisor: N64) -> Self::Output {
+        self /= divisor;
+        self
+    }
+}
+
+/// Rescale the four-momentum, keeping the user index
+impl MulAssign<f64> for PseudoJet {
+    fn mul_assign(&mut self, factor: f64) {
+        *self *= n64(factor)
+    }
+}
+
+/// Rescale the four-momentum, keeping the user index
+impl Mul<f64> for PseudoJet {
+    type Output = Self;
+
+    fn mul(self, factor: f64) -> Self::Output {
+        self * n64(factor)
+    }
+}
+
+/// Rescale the four-momentum, keeping the user index
+impl Mul<PseudoJet> for f64 {
+    type Output = PseudoJet;
+
+    fn mul(self, p: PseudoJet) -> Self::Output {
+        p * self
+    }
+}
+
+/// Rescale the four-momentum, keeping the user index
+impl DivAssign<f64> for PseudoJet {
+    fn div_assign(&mut self, divisor: f64) {
+        *self /= n64(divisor)
+    }
+}
+
+/// Rescale the four-momentum, keeping the user index
+impl Div<f64> for PseudoJet {
+    type Output = Self;
+
+    fn div(self, divisor: f64) -> Self::Output {
+        self / n64(divisor)
+    }
+}
+
+/// Flip the sign of the four-momentum, keeping the user index
+impl Neg for PseudoJet {
+    type Output = Self;
+
+    fn neg(mut self) -> Self::Output {
+        self *= n64(-1.);
+        self
+    }
+}
+
+impl Sum for PseudoJet {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::new(), |sum, p| sum + p)
+    }
+}
+
+impl<'a> Sum<&'a PseudoJet> for PseudoJet {
+    fn sum<I: Iterator<Item = &'a PseudoJet>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+/// Lorentz boosts and rotations
+///
+/// All transformations keep the user index.
+impl PseudoJet {
+    /// Velocity `p/E` of the frame in which the pseudojet is at rest
+    pub fn boost_vector(&self) -> [N64; 3] {
+        let e = self.e();
+        [self.px() / e, self.py() / e, self.pz() / e]
+    }
+
+    /// Apply a Lorentz boost with velocity `beta`
+    ///
+    /// A pseudojet at rest ends up with velocity `beta` after the
+    /// boost. Panics if the absolute value of `beta` is not below 1.
+    pub fn boost(&mut self, beta: &[N64; 3]) {
+        let beta2: N64 = beta.iter().map(|&b| b * b).sum();
+        assert!(beta2 < 1., "boost velocity must be below 1");
+        if beta2 == 0. {
+            return;
+        }
+        let gamma = n64(1.) / (n64(1.) - beta2).sqrt();
+        let [e, px, py, pz] = self.comp;
+        let beta_p = beta[0] * px + beta[1] * py + beta[2] * pz;
+        let coeff = gamma * gamma / (gamma + 1.) * beta_p + gamma * e;
+        self.comp = [
+            gamma * (e + beta_p),
+            px + coeff * beta[0],
+            py + coeff * beta[1],
+            pz + coeff * beta[2],
+        ];
+        self.init_pt2_phi_rap()
+    }
+
+    /// Boost into the rest frame of `other`
+    ///
+    /// `other` must be time-like.
+    pub fn boost_to_rest_frame_of(&mut self, other: &PseudoJet) {
+        self.boost(&other.boost_vector().map(|b| -b))
+    }
+
+    /// Undo [boost_to_rest_frame_of](Self::boost_to_rest_frame_of)
+    ///
+    /// If `self` is in the rest frame of `other`, boost it back into
+    /// the frame where `other` has its actual momentum.
+    pub fn unboost(&mut self, other: &PseudoJet) {
+        self.boost(&other.boost_vector())
+    }
+
+    /// Rotate by `angle` around the z axis
+    pub fn rotate_z(&mut self, angle: N64) {
+        let (sin, cos) = (angle.sin(), angle.cos());
+        let [_, px, py, _] = self.comp;
+        self.comp[1] = cos * px - sin * py;
+        self.comp[2] = sin * px + cos * py;
+        self.init_pt2_phi_rap()
+    }
+
+    /// Rotate by `angle` around `axis`
+    ///
+    /// The rotation is counter-clockwise when looking against the
+    /// direction of `axis`, which does not have to be normalised.
+    /// Panics if `axis` is the null vector.
+    pub fn rotate(&mut self, axis: &[N64; 3], angle: N64) {
+        let norm = axis.iter().map(|&a| a * a).sum::<N64>().sqrt();
+        assert!(norm > 0., "rotation around null vector");
+        let n = axis.map(|a| a / norm);
+        let [_, px, py, pz] = self.comp;
+        let p = [px, py, pz];
+        let n_dot_p = n[0] * px + n[1] * py + n[2] * pz;
+        let n_cross_p = [
+            n[1] * pz - n[2] * py,
+            n[2] * px - n[0] * pz,
+            n[0] * py - n[1] * px,
+        ];
+        let (sin, cos) = (angle.sin(), angle.cos());
+        for i in 0..3 {
+            self.comp[i + 1] = cos * p[i]
+                + sin * n_cross_p[i]
+                + (n64(1.) - cos) * n_dot_p * n[i];
+        }
+        self.init_pt2_phi_rap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: N64, b: f64) {
+        assert!(f64::from((a - b).abs()) < 1e-10, "{a} != {b}");
+    }
+
+    #[test]
+    fn tst_kinematics() {
+        let p = pseudojet_f(5., 1., 2., 2.);
+        assert_close(p.modp2(), 9.);
+        assert_close(p.modp(), 3.);
+        assert_close(p.m2(), 16.);
+        assert_close(p.m(), 4.);
+        assert_close(p.mt2(), 21.);
+        assert_close(p.mt(), 21f64.sqrt());
+        assert_close(p.et2(), 25. * 5. / 9.);
+        assert_close(p.et(), 5. * 5f64.sqrt() / 3.);
+        assert_close(p.theta(), (5f64.sqrt() / 2.).atan());
+        assert_close(p.eta(), -f64::from(p.theta() / 2.).tan().ln());
+        assert_close(p.beta(), 0.6);
+
+        let spacelike = pseudojet_f(1., 0., 0., 2.);
+        assert_close(spacelike.m2(), -3.);
+        assert_close(spacelike.m(), -(3f64.sqrt()));
+        assert_close(spacelike.rap(), spacelike.eta().into());
+
+        let massless = pseudojet_f(2., 0., 0., 2.);
+        assert_close(massless.rap(), 1e5 + 2.);
+        assert_close(massless.delta_r2(&massless), 0.);
+    }
+
+    #[test]
+    fn tst_kinematics_on_axis() {
+        let p = pseudojet_f(2., 0., 0., -1.);
+        assert_close(p.eta(), -1e5 - 1.);
+        assert_close(p.theta(), PI);
+        assert_close(p.et(), 0.);
+        assert_close(p.m(), 3f64.sqrt());
+
+        let p = pseudojet_f(1., 0., 0., 0.);
+        assert_close(p.eta(), 0.);
+        assert_close(p.theta(), 0.);
+        assert_close(p.et(), 0.);
+        assert_close(p.beta(), 0.);
+        assert_close(p.m(), 1.);
+
+        let p = PseudoJet::new();
+        assert_close(p.beta(), 0.);
+        assert_close(p.m(), 0.);
+    }
+
+    fn assert_close_mom(p: &PseudoJet, q: &PseudoJet) {
+        for i in 0..D {
+            assert_close(p[i], q[i].into());
+        }
+    }
+
+    fn assert_close_jet(p: &PseudoJet, q: &PseudoJet) {
+        assert_close_mom(p, q);
+        assert_close(p.rap(), q.rap().into());
+        assert_close(p.phi(), q.phi().into());
+        assert_close(p.inv_pt2(), q.inv_pt2().into());
+    }
+
+    #[test]
+    fn tst_arithmetic() {
+        let p = pseudojet_f(5., 1., 2., 2.).with_user_index(1);
+        let q = pseudojet_f(3., 0., -1., 2.);
+
+        let scaled = 2.5 * p;
+        assert_close_jet(&scaled, &pseudojet_f(12.5, 2.5, 5., 5.));
+        assert_eq!(scaled.user_index(), Some(1));
+        assert_eq!(scaled, pseudojet_f(12.5, 2.5, 5., 5.).with_user_index(1));
+        assert_close_jet(&(scaled / 2.5), &p);
+        assert_close_mom(&(p * 0.), &PseudoJet::new());
+
+        let neg = -p;
+        assert_close_jet(&neg, &pseudojet_f(-5., -1., -2., -2.));
+        assert_close_jet(&(neg + p + q), &q);
+
+        assert_close(p.dot(&p), p.m2().into());
+        assert_close(p.dot(&q), 15. + 2. - 4.);
+        assert_close((p + q).m2(), f64::from(p.m2() + q.m2() + p.dot(&q) * 2.));
+
+        let partons = [p, q, scaled];
+        let sum: PseudoJet = partons.iter().sum();
+        assert_close_jet(&sum, &(p + q + scaled));
+        assert_eq!(partons.into_iter().sum::<PseudoJet>(), sum);
+        assert_eq!(sum.user_index(), None);
+    }
+
+    #[test]
+    fn tst_setters() {
+        let mut p = pseudojet_f(5., 1., 2., 2.).with_user_index(1);
+        p.set_e(n64(6.));
+        p.set_px(n64(-1.));
+        p.set_py(n64(0.));
+        p.set_comp(3, n64(-3.));
+        assert_close_jet(&p, &pseudojet_f(6., -1., 0., -3.));
+        p.reset_momentum(n64(4.), n64(0.), n64(3.), n64(1.));
+        assert_close_jet(&p, &pseudojet_f(4., 0., 3., 1.));
+        assert_eq!(p.user_index(), Some(1));
+    }
+
+    #[test]
+    fn tst_boost() {
+        let q = pseudojet_f(10., 1., -2., 3.);
+        let mut rest = q;
+        rest.boost_to_rest_frame_of(&q);
+        assert_close_mom(&rest, &pseudojet_f(q.m().into(), 0., 0., 0.));
+        rest.unboost(&q);
+        assert_close_jet(&rest, &q);
+
+        let orig = pseudojet_f(5., 1., 2., 2.).with_user_index(3);
+        let mut p = orig;
+        p.boost_to_rest_frame_of(&q);
+        assert_close(p.m(), orig.m().into());
+        let mut sum = orig + q;
+        sum.boost_to_rest_frame_of(&q);
+        assert_close_mom(&sum, &(p + pseudojet_f(q.m().into(), 0., 0., 0.)));
+        p.unboost(&q);
+        assert_close_jet(&p, &orig);
+        assert_eq!(p.user_index(), Some(3));
+
+        // boost along z shifts the rapidity
+        let mut p = orig;
+        p.boost(&[n64(0.), n64(0.), n64(0.5)]);
+        assert_close(p.rap(), f64::from(orig.rap()) + 0.5f64.atanh());
+        assert_close(p.phi(), orig.phi().into());
+    }
+
+    #[test]
+    #[should_panic(expected = "boost velocity")]
+    fn tst_boost_superluminal() {
+        let mut p = pseudojet_f(5., 1., 2., 2.);
+        p.boost(&[n64(0.6), n64(0.), n64(0.8)]);
+    }
+
+    #[test]
+    fn tst_rotate() {
+        let orig = pseudojet_f(5., 1., 2., 2.);
+        let mut p = orig;
+        p.rotate_z(n64(-2.));
+        assert_eq!(p, pseudojet(p.e(), p.px(), p.py(), p.pz()));
+        assert_close(p.phi(), f64::from(orig.phi()) - 2. + 2. * PI);
+        let mut q = orig;
+        q.rotate(&[n64(0.), n64(0.), n64(2.)], n64(-2.));
+        assert_close_jet(&p, &q);
+
+        let mut p = orig;
+        p.rotate(&[n64(1.), n64(0.), n64(0.)], n64(PI / 2.));
+        assert_close_jet(&p, &pseudojet_f(5., 1., -2., 2.));
+        p.rotate(&[n64(1.), n64(-1.), n64(1.)], n64(0.7));
+        assert_close(p.m(), orig.m().into());
+        assert_close(p.modp(), orig.modp().into());
+    }
+
+    #[test]
+    fn tst_try_new() {
+        assert!(PseudoJet::try_new(5., 1., 2., 2.).is_ok());
+        assert!(PseudoJet::try_new(3., 0., 0., 3. + 1e-12).is_ok());
+        assert_eq!(
+            PseudoJet::try_new(5., 1., f64::NAN, 2.),
+            Err(Error::NaN(2))
+        );
+        assert_eq!(
+            PseudoJet::try_new(f64::INFINITY, 1., 2., 2.),
+            Err(Error::Infinite(0))
+        );
+        assert_eq!(
+            PseudoJet::try_new(2., 0., 0., -3.),
+            Err(Error::Spacelike { e: 2., modp: 3. })
+        );
+        assert!(matches!(
+            PseudoJet::try_new(-1., 0., 0., 0.),
+            Err(Error::Spacelike { .. })
+        ));
+
+        let comp = [5., 1., 2., 2.];
+        assert_eq!(
+            PseudoJet::try_from(&comp[..]),
+            Ok(pseudojet_f(5., 1., 2., 2.))
+        );
+        assert_eq!(PseudoJet::try_from(&comp[1..]), Err(Error::WrongLength(3)));
+        assert!(pseudojet_f(2., 0., 0., 3.).check().is_err());
+    }
+
+    #[test]
+    fn tst_from_pt_y_phi() {
+        let (pt, y, phi, m) = (n64(12.), n64(-1.3), n64(5.), n64(3.));
+        let p = PseudoJet::from_pt_y_phi_m(pt, y, phi, m);
+        let p_recalc = pseudojet(p.e(), p.px(), p.py(), p.pz());
+        assert_eq!(p, p_recalc);
+        for p in [p, p_recalc] {
+            assert_close(p.pt(), pt.into());
+            assert_close(p.rap(), y.into());
+            assert_close(p.phi(), phi.into());
+            assert_close(p.m(), m.into());
+        }
+
+        let p = PseudoJet::from_pt_y_phi_e(pt, y, phi, p.e());
+        assert_close(p.m(), m.into());
+        assert_eq!(p, pseudojet(p.e(), p.px(), p.py(), p.pz()));
+
+        let p = PseudoJet::from_pt_y_phi_m(pt, y, n64(-1.), m);
+        assert_close(p.phi(), 2. * PI - 1.);
+    }
+
+    #[test]
+    fn tst_from_pt_eta_phi() {
+        let (pt, eta, phi, m) = (n64(12.), n64(2.1), n64(0.3), n64(3.));
+        let p = PseudoJet::from_pt_eta_phi_m(pt, eta, phi, m);
+        assert_close(p.pt(), pt.into());
+        assert_close(p.eta(), eta.into());
+        assert_close(p.phi(), phi.into());
+        assert_close(p.m(), m.into());
+
+        let p = PseudoJet::from_pt_eta_phi_e(pt, eta, phi, p.e());
+        assert_close(p.m(), m.into());
+
+        let massless = PseudoJet::from_pt_eta_phi_m(pt, eta, phi, n64(0.));
+        assert_close(massless.rap(), eta.into());
+    }
+
+    #[test]
+    fn tst_kt_distance() {
+        let p1 = pseudojet_f(5., 3., 0., 0.);
+        let p2 = pseudojet_f(5., 0., 1., 0.);
+        assert_close(p1.kt_distance(&p2), PI * PI / 4.);
+    }
+}
@@ -0,0 +1,163 @@
+use crate::pseudojet::PseudoJet;
+
+use std::f64::consts::PI;
+
+use noisy_float::prelude::*;
+
+/// Recombination scheme used to combine two pseudojets into one
+pub trait Recombiner {
+    /// Combine two pseudojets
+    fn recombine(&self, p1: &PseudoJet, p2: &PseudoJet) -> PseudoJet;
+}
+
+/// E scheme: add the four-momenta
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct EScheme;
+
+impl Recombiner for EScheme {
+    fn recombine(&self, p1: &PseudoJet, p2: &PseudoJet) -> PseudoJet {
+        *p1 + *p2
+    }
+}
+
+/// pt scheme: add transverse momenta and take the pt-weighted average
+/// of rapidity and azimuthal angle
+///
+/// The resulting pseudojet is massless.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct PtScheme;
+
+impl Recombiner for PtScheme {
+    fn recombine(&self, p1: &PseudoJet, p2: &PseudoJet) -> PseudoJet {
+        weighted_recombine(p1, p2, p1.pt(), p2.pt(), |p| p.rap())
+    }
+}
+
+/// pt^2 scheme: add transverse momenta and take the pt^2-weighted
+/// average of rapidity and azimuthal angle
+///
+/// The resulting pseudojet is massless.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Pt2Scheme;
+
+impl Recombiner for Pt2Scheme {
+    fn recombine(&self, p1: &PseudoJet, p2: &PseudoJet) -> PseudoJet {
+        weighted_recombine(p1, p2, p1.pt2(), p2.pt2(), |p| p.rap())
+    }
+}
+
+/// Et scheme: add transverse energies and take the Et-weighted average
+/// of pseudorapidity and azimuthal angle
+///
+/// The resulting pseudojet is massless with a transverse momentum
+/// equal to the sum of the transverse energies.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct EtScheme;
+
+impl Recombiner for EtScheme {
+    fn recombine(&self, p1: &PseudoJet, p2: &PseudoJet) -> PseudoJet {
+        let [et1, et2] = [p1.et(), p2.et()];
+        let sum = et1 + et2;
+        if sum == 0. {
+            return *p1 + *p2;
+        }
+        let eta = (et1 * p1.eta() + et2 * p2.eta()) / sum;
+        let phi = weighted_phi(p1, p2, et1, et2);
+        PseudoJet::from_pt_y_phi_m(sum, eta, phi, n64(0.))
+    }
+}
+
+/// Winner-takes-all pt scheme: add transverse momenta and take the
+/// rapidity, azimuthal angle, and mass of the pseudojet with larger pt
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct WtaPtScheme;
+
+impl Recombiner for WtaPtScheme {
+    fn recombine(&self, p1: &PseudoJet, p2: &PseudoJet) -> PseudoJet {
+        let winner = if p1.pt2() >= p2.pt2() { p1 } else { p2 };
+        let pt = p1.pt() + p2.pt();
+        PseudoJet::from_pt_y_phi_m(pt, winner.rap(), winner.phi(), winner.m())
+    }
+}
+
+impl<T: Recombiner> Recombiner for &T {
+    fn recombine(&self, p1: &PseudoJet, p2: &PseudoJet) -> PseudoJet {
+        (*self).recombine(p1, p2)
+    }
+}
+
+// Add the transverse momenta and take the weighted average of the
+// azimuthal angle and the longitudinal variable `long`
+fn weighted_recombine(
+    p1: &PseudoJet,
+    p2: &PseudoJet,
+    w1: N64,
+    w2: N64,
+    long: impl Fn(&PseudoJet) -> N64,
+) -> PseudoJet {
+    let sum = w1 + w2;
+    if sum == 0. {
+        return *p1 + *p2;
+    }
+    let y = (w1 * long(p1) + w2 * long(p2)) / sum;
+    let phi = weighted_phi(p1, p2, w1, w2);
+    PseudoJet::from_pt_y_phi_m(p1.pt() + p2.pt(), y, phi, n64(0.))
+}
+
+// Weighted average of the azimuthal angles, taking into account
+// that φ is periodic
+fn weighted_phi(p1: &PseudoJet, p2: &PseudoJet, w1: N64, w2: N64) -> N64 {
+    let phi1 = p1.phi();
+    let mut phi2 = p2.phi();
+    if phi2 - phi1 > PI {
+        phi2 -= 2. * PI;
+    } else if phi1 - phi2 > PI {
+        phi2 += 2. * PI;
+    }
+    (w1 * phi1 + w2 * phi2) / (w1 + w2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pseudojet_f;
+
+    fn assert_close(a: N64, b: N64) {
+        assert!(f64::from((a - b).abs()) < 1e-10, "{a} != {b}");
+    }
+
+    #[test]
+    fn tst_pt_scheme() {
+        // φ = 0.1 and φ = 2π - 0.1 with the same pt
+        let p1 = pseudojet_f(2., 0.1f64.cos(), 0.1f64.sin(), 0.5);
+        let p2 = pseudojet_f(2., 0.1f64.cos(), -0.1f64.sin(), -0.5);
+        for p in [PtScheme.recombine(&p1, &p2), Pt2Scheme.recombine(&p1, &p2)] {
+            assert_close(p.pt(), n64(2.));
+            assert_close(p.rap(), n64(0.));
+            assert_close(p.py(), n64(0.));
+            assert_close(p.e(), n64(2.));
+        }
+    }
+
+    #[test]
+    fn tst_et_scheme() {
+        let p1 = pseudojet_f(3., 1., 0., 0.);
+        let p2 = pseudojet_f(5., 0., 3., 0.);
+        let p = EtScheme.recombine(&p1, &p2);
+        assert_close(p.pt(), n64(8.));
+        assert_close(p.rap(), n64(0.));
+        assert_close(p.phi(), n64(5. / 8. * PI / 2.));
+    }
+
+    #[test]
+    fn tst_wta_pt_scheme() {
+        let p1 = pseudojet_f(3., 1., 0., 2.);
+        let p2 = pseudojet_f(5., 0., 3., 0.);
+        let p = WtaPtScheme.recombine(&p1, &p2);
+        assert_close(p.pt(), n64(4.));
+        assert_close(p.rap(), p2.rap());
+        assert_close(p.phi(), p2.phi());
+        assert_close(p.m(), p2.m());
+        assert_close(p.e(), n64(32.).sqrt());
+    }
+}
@@ -1,5 +1,37 @@
+use std::f64::consts::PI;
+
+use noisy_float::prelude::*;
+
 use crate::{pseudojet_f, PseudoJet};
 
+// Constituents of a wide jet: two prongs of 30 massless particles
+// each with total transverse momenta 300 and 240 and ΔR = 2 between
+// them, plus a very soft forward particle
+pub(crate) fn wide_jet() -> ([Vec<PseudoJet>; 2], PseudoJet) {
+    let prongs = [
+        spray(30, 300., [0., 1.], 0.05),
+        spray(30, 240., [2., 1.], 0.05),
+    ];
+    let forward =
+        PseudoJet::from_pt_y_phi_m(n64(0.1), n64(7.), n64(1.), n64(0.));
+    (prongs, forward)
+}
+
+// `n` massless particles with total scalar transverse momentum `pt`,
+// evenly spaced on a circle with radius `r` around `y`, `phi` in the
+// rapidity-azimuth plane
+fn spray(n: usize, pt: f64, [y, phi]: [f64; 2], r: f64) -> Vec<PseudoJet> {
+    Vec::from_iter((0..n).map(|i| {
+        let angle = 2. * PI * i as f64 / n as f64;
+        PseudoJet::from_pt_y_phi_m(
+            n64(pt / n as f64),
+            n64(y + r * angle.cos()),
+            n64(phi + r * angle.sin()),
+            n64(0.),
+        )
+    }))
+}
+
 pub(crate) fn partons_9_to_7() -> Vec<PseudoJet> {
     vec![
         pseudojet_f(